@@ -1,35 +1,44 @@
 use bevy::prelude::*;
 
-use crate::{items::ItemType, structures::StructureType};
+use crate::{
+    input::mouse::{FaeEntityClickEvent, FaeEntityContextClickEvent},
+    items::ItemType,
+    structures::StructureType,
+};
 
-struct FaeCommonPlugin;
+pub struct FaeCommonPlugin;
 
 impl Plugin for FaeCommonPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<GameState>()
+        app.add_state::<GameState>()
+            .register_type::<GameState>()
             .register_type::<MainMenuUI>()
             .register_type::<FaeGameCamera>()
             .register_type::<Speed>()
             .register_type::<Clickable>()
             .register_type::<Hoverable>()
+            .register_type::<Paused>()
             .register_type::<Held>()
-            .register_type::<Holdable>();
+            .register_type::<HeldFacing>()
+            .register_type::<Holdable>()
+            .insert_resource(TextInputFocused::default())
+            .register_type::<TextInputFocused>()
+            .insert_resource(HeldFacing::default())
+            // Sent by the mouse input but read by gameplay, which also runs without a window
+            .add_event::<FaeEntityClickEvent>()
+            .add_event::<FaeEntityContextClickEvent>();
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Reflect)]
+/// Gameplay systems only run while `Playing`, so pausing freezes the factory.
+#[derive(States, Debug, Clone, Copy, Eq, PartialEq, Hash, Reflect, Default)]
 pub enum GameState {
+    #[default]
     MainMenu,
     Playing,
     Paused,
 }
 
-impl Default for GameState {
-    fn default() -> Self {
-        GameState::Playing
-    }
-}
-
 #[derive(Component, Reflect)]
 pub struct MainMenuUI;
 
@@ -45,40 +54,76 @@ pub struct Clickable;
 #[derive(Component, Reflect, Default)]
 pub struct Hoverable;
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+/// Stops a structure from crafting, gathering, studying or moving items until removed.
+#[derive(Component, Reflect, Default)]
+pub struct Paused;
+
+#[derive(Component, PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Default)]
 pub enum Facing {
     Left,
+    #[default]
     Right,
     Top,
     Bottom,
 }
 
+impl Facing {
+    pub fn rotated_clockwise(&self) -> Self {
+        use Facing::*;
+        match self {
+            Right => Bottom,
+            Bottom => Left,
+            Left => Top,
+            Top => Right,
+        }
+    }
+
+    /// Rotation around the z axis for sprites, with `Right` being unrotated.
+    pub fn angle(&self) -> f32 {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        use Facing::*;
+        match self {
+            Right => 0.0,
+            Top => FRAC_PI_2,
+            Left => PI,
+            Bottom => -FRAC_PI_2,
+        }
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_rotation_z(self.angle())
+    }
+
+    /// The grid offset of the tile this facing points at.
+    pub fn offset(&self) -> IVec2 {
+        use Facing::*;
+        match self {
+            Left => IVec2::new(-1, 0),
+            Right => IVec2::new(1, 0),
+            Top => IVec2::new(0, 1),
+            Bottom => IVec2::new(0, -1),
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
 pub enum Holdable {
     Item(ItemType),
     Structure(StructureType),
 }
 
-#[derive(Component, Reflect, Default)]
-pub struct TimedProcessState {
-    pub duration: f32,
-    pub timer: Timer,
-}
-
 #[derive(Component, Reflect, Debug)]
 pub struct Held(pub Option<Holdable>);
 
-pub fn round_to_grid(pos: Vec2) -> Vec2 {
-    let grid_step = 20.0;
-    let round_up = |num: f32| -> f32 {
-        let remainder = num % grid_step;
-        if remainder == 0.0 {
-            num
-        } else {
-            num + grid_step - remainder
-        }
-    };
-    let x = round_up(pos.x.round()) as i32;
-    let y = round_up(pos.y.round()) as i32;
-    Vec2::new(x as f32, y as f32)
+/// The facing a held structure will be placed with.
+#[derive(Resource, Reflect, Debug, Default)]
+pub struct HeldFacing(pub Facing);
+
+/// Set by the ui while a text field has the keyboard.
+#[derive(Resource, Reflect, Debug, Default)]
+pub struct TextInputFocused(pub bool);
+
+/// Run condition for keyboard controls that shouldn't fire while typing.
+pub fn text_input_unfocused(focused: Res<TextInputFocused>) -> bool {
+    !focused.0
 }
@@ -1,16 +1,29 @@
 use bevy::prelude::*;
 
-use crate::{items::inventory::Inventory, player::Player, recipes::Recipe};
+use crate::{
+    common::{GameState, Paused},
+    items::{
+        definitions::ItemRegistry,
+        inventory::{Inventory, ItemAmount},
+    },
+    recipes::{CraftingCategory, Recipe},
+    simulation::NORMAL_SPEED,
+};
 
 pub struct CraftingPlugin;
 
 impl Plugin for CraftingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, handle_crafting)
-            .add_systems(Update, cancel_player_crafting)
-            .register_type::<Crafter>()
-            .register_type::<Recipe>()
-            .add_event::<CraftCompleteEvent>();
+        app.add_systems(
+            FixedUpdate,
+            handle_crafting.run_if(in_state(GameState::Playing)),
+        )
+        .register_type::<Crafter>()
+        .register_type::<CraftingCategories>()
+        .register_type::<CraftingBlocked>()
+        .register_type::<Recipe>()
+        .add_event::<CraftCompleteEvent>()
+        .add_event::<ItemsConsumedEvent>();
     }
 }
 
@@ -20,13 +33,18 @@ pub struct CraftCompleteEvent {
     pub recipe: Recipe,
 }
 
-#[derive(Component, Reflect, Default)]
+/// Items a structure used up outside of a recipe, like fuel burnt or research studied.
+#[derive(Event)]
+pub struct ItemsConsumedEvent {
+    pub items: Vec<ItemAmount>,
+}
+
+#[derive(Component, Reflect, Debug, Default, Clone)]
 pub struct Crafter {
     pub recipe: Option<Recipe>,
-    pub progress: f32,
+    // Ticks of work at normal speed, times `NORMAL_SPEED`
+    pub progress: u32,
     pub state: CrafterState,
-    //progress_bar: Handle<ColorMaterial>,
-    //progress_bar_bg: Handle<ColorMaterial>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Reflect)]
@@ -42,27 +60,60 @@ impl Default for CrafterState {
     }
 }
 
+/// Crafting or study speed as a percentage, 100 being normal.
 #[derive(Component, Reflect)]
-pub struct CrafterSpeed(pub f32);
+pub struct CrafterSpeed(pub u32);
+
+/// Set by whatever powers a crafter, like a smelter's fuel, while its craft can't progress.
+#[derive(Component, Reflect, Default, Debug)]
+pub struct CraftingBlocked(pub bool);
+
+/// The kinds of recipe a crafter accepts.
+#[derive(Component, Reflect, Default, Clone, Debug)]
+pub struct CraftingCategories(pub Vec<CraftingCategory>);
+
+impl CraftingCategories {
+    pub fn supports(&self, recipe: &Recipe) -> bool {
+        recipe
+            .categories
+            .iter()
+            .any(|category| self.0.contains(category))
+    }
+}
 
 impl Crafter {
     pub fn new() -> Self {
         Crafter {
             recipe: None,
-            progress: 0.0,
+            progress: 0,
             state: CrafterState::Idle,
-            //progress_bar: asset_server.load("progress_bar.png"),
-            //progress_bar_bg: asset_server.load("progress_bar_bg.png"),
         }
     }
+
+    /// Whether the current craft is done and only waiting for room for its output.
+    pub fn is_finished(&self, recipe: &Recipe) -> bool {
+        self.progress >= recipe.cost_ticks() * NORMAL_SPEED
+    }
+
+    pub fn progress_fraction(&self, recipe: &Recipe) -> f32 {
+        (self.progress as f32 / (recipe.cost_ticks() * NORMAL_SPEED) as f32).clamp(0.0, 1.0)
+    }
 }
 
-fn handle_crafting(
-    time: Res<Time>,
-    mut assemblers: Query<(&mut Crafter, &mut Inventory, Option<&CrafterSpeed>, Entity)>,
+type CrafterComponents = (
+    &'static mut Crafter,
+    &'static mut Inventory,
+    Option<&'static CrafterSpeed>,
+    Option<&'static CraftingBlocked>,
+    Entity,
+);
+
+pub fn handle_crafting(
+    mut assemblers: Query<CrafterComponents, Without<Paused>>,
     mut events: EventWriter<CraftCompleteEvent>,
+    registry: Res<ItemRegistry>,
 ) {
-    for (mut assembler, mut inventory, speed, entity) in &mut assemblers {
+    for (mut assembler, mut inventory, speed, blocked, entity) in &mut assemblers {
         match assembler.state {
             CrafterState::Idle => (),
             CrafterState::Pending(repeating) => {
@@ -73,44 +124,37 @@ fn handle_crafting(
                 }
             }
             CrafterState::Assembling(repeating) => {
+                if blocked.is_some_and(|blocked| blocked.0) {
+                    continue;
+                }
+                // Borrow the fields separately so progress can change while reading the recipe
+                let assembler = &mut *assembler;
                 if let Some(recipe) = assembler.recipe.as_ref() {
-                    let crafting_speed = speed.map_or(1.0, |s| s.0);
-                    if assembler.progress + time.delta_seconds() * crafting_speed >= recipe.cost {
+                    let crafting_speed = speed.map_or(NORMAL_SPEED, |s| s.0);
+                    let total = recipe.cost_ticks() * NORMAL_SPEED;
+                    assembler.progress = (assembler.progress + crafting_speed).min(total);
+                    if assembler.progress >= total {
+                        if !inventory.has_space_for(&recipe.output, &registry) {
+                            // Hold the finished craft until the output has somewhere to go
+                            continue;
+                        }
                         // Notify that crafting is complete
                         events.send(CraftCompleteEvent {
                             entity,
                             recipe: recipe.clone(),
                         });
                         // Update the items
-                        inventory.add_items(&recipe.output);
+                        inventory.add_items(&recipe.output, &registry);
 
                         assembler.state = match repeating {
                             true => CrafterState::Pending(true),
                             false => CrafterState::Idle,
                         };
 
-                        assembler.progress = 0.0;
-                    } else {
-                        assembler.progress += time.delta_seconds();
+                        assembler.progress = 0;
                     }
                 }
             }
         }
     }
 }
-
-fn cancel_player_crafting(
-    input: Res<Input<KeyCode>>,
-    mut assemblers: Query<(&mut Crafter, &mut Inventory), With<Player>>,
-) {
-    for (mut assembler, mut inventory) in &mut assemblers {
-        if input.just_pressed(KeyCode::X) {
-            if let Some(ref recipe) = &assembler.recipe {
-                inventory.add_items(&recipe.input);
-            }
-            assembler.state = CrafterState::Idle;
-            assembler.progress = 0.0;
-            assembler.recipe = None;
-        }
-    }
-}
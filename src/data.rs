@@ -0,0 +1,82 @@
+use std::marker::PhantomData;
+
+use bevy::{
+    asset::{Asset, AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::de::DeserializeOwned;
+
+/// An asset file of definitions, loaded from every matching file in `FOLDER`.
+pub trait DataSet: Asset + DeserializeOwned {
+    type Definition: Clone;
+
+    const FOLDER: &'static str;
+    const EXTENSIONS: &'static [&'static str];
+
+    fn definitions(&self) -> &[Self::Definition];
+}
+
+/// Loads and hot reloads every `T` file in its asset folder.
+/// Owners rebuild their data on `AssetEvent<T>` using `loaded_definitions`.
+pub struct DataFolderPlugin<T>(PhantomData<T>);
+
+impl<T> Default for DataFolderPlugin<T> {
+    fn default() -> Self {
+        DataFolderPlugin(PhantomData)
+    }
+}
+
+impl<T: DataSet> Plugin for DataFolderPlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<T>()
+            .add_asset_loader(DataSetLoader::<T>(PhantomData))
+            .add_systems(Startup, load_data_folder::<T>);
+    }
+}
+
+/// Every definition across the loaded sets, so edits and removals are picked up on rebuild.
+pub fn loaded_definitions<T: DataSet>(
+    sets: &Assets<T>,
+) -> impl Iterator<Item = T::Definition> + '_ {
+    sets.iter()
+        .flat_map(|(_, set)| set.definitions().iter().cloned())
+}
+
+struct DataSetLoader<T>(PhantomData<T>);
+
+impl<T: DataSet> AssetLoader for DataSetLoader<T> {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let set = ron::de::from_bytes::<T>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(set));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        T::EXTENSIONS
+    }
+}
+
+// Keeps the folder's handles alive so the sets stay loaded and get hot reloaded
+#[derive(Resource)]
+struct DataFolderHandles<T> {
+    _handles: Vec<HandleUntyped>,
+    _set: PhantomData<T>,
+}
+
+fn load_data_folder<T: DataSet>(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = asset_server.load_folder(T::FOLDER).unwrap_or_else(|error| {
+        println!("Failed to load data from {}: {:?}", T::FOLDER, error);
+        vec![]
+    });
+    commands.insert_resource(DataFolderHandles::<T> {
+        _handles: handles,
+        _set: PhantomData,
+    });
+}
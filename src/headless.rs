@@ -0,0 +1,195 @@
+use std::{path::PathBuf, time::Duration};
+
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin},
+    input::InputPlugin,
+    prelude::*,
+    time::TimeUpdateStrategy,
+    utils::HashMap,
+};
+
+use crate::{
+    common::{FaeCommonPlugin, GameState},
+    crafting::{CraftCompleteEvent, CraftingPlugin},
+    items::{definitions::ItemRegistry, item_spawner::ItemSpawnEvent, ItemPlugin, ItemType},
+    map::MapPlugin,
+    player::PlayerPlugin,
+    recipes::{RecipePlugin, Recipes},
+    research::{technology::Technologies, ResearchPlugin},
+    save::{LoadGameEvent, SavePlugin},
+    simulation::{tick_duration, SimulationPlugin, TICKS_PER_SECOND},
+    structures::{Structure, StructurePlugin},
+};
+
+// Give up if the data files haven't loaded after this long
+const LOAD_TIMEOUT_SECONDS: f32 = 30.0;
+const DEFAULT_MINUTES: f64 = 10.0;
+const USAGE: &str = "Usage: fae_factory --headless <save file> [minutes]";
+
+/// What to simulate when running with `--headless <save file> [minutes]`.
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub save: PathBuf,
+    pub minutes: f64,
+}
+
+impl HeadlessOptions {
+    /// Picks the options out of the command line, if `--headless` was passed.
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+        let Some(index) = args.iter().position(|arg| arg == "--headless") else {
+            return Ok(None);
+        };
+        let Some(save) = args.get(index + 1) else {
+            return Err(USAGE.to_string());
+        };
+        let minutes = match args.get(index + 2) {
+            Some(minutes) => minutes
+                .parse::<f64>()
+                .ok()
+                .filter(|minutes| minutes.is_finite() && *minutes > 0.0)
+                .ok_or_else(|| format!("Invalid minutes {:?}\n{}", minutes, USAGE))?,
+            None => DEFAULT_MINUTES,
+        };
+        Ok(Some(HeadlessOptions {
+            save: PathBuf::from(save),
+            minutes,
+        }))
+    }
+}
+
+#[derive(Resource, Debug)]
+enum HeadlessRun {
+    Loading { save: PathBuf, ticks: u64 },
+    Starting { ticks: u64 },
+    Simulating { ticks: u64, remaining: u64 },
+}
+
+/// Items produced by crafters and gatherers since the save was loaded.
+#[derive(Resource, Default, Debug)]
+struct ProductionCounts(HashMap<ItemType, u32>);
+
+/// Runs a saved factory without a window as fast as possible and prints what it produced.
+pub fn run_headless(options: HeadlessOptions) {
+    let ticks = ((options.minutes * 60.0 * TICKS_PER_SECOND as f64).round() as u64).max(1);
+    println!(
+        "Simulating {:?} for {} minutes ({} ticks)",
+        options.save, options.minutes, ticks
+    );
+    App::new()
+        .add_plugins((
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::ZERO)),
+            AssetPlugin::default(),
+            InputPlugin,
+        ))
+        .add_plugins((
+            FaeCommonPlugin,
+            SimulationPlugin,
+            PlayerPlugin,
+            CraftingPlugin,
+            RecipePlugin,
+            ItemPlugin,
+            StructurePlugin,
+            ResearchPlugin,
+            MapPlugin,
+            SavePlugin,
+        ))
+        .insert_resource(HeadlessRun::Loading {
+            save: options.save,
+            ticks,
+        })
+        .insert_resource(ProductionCounts::default())
+        .add_systems(
+            Update,
+            (
+                count_production.run_if(in_state(GameState::Playing)),
+                drive_headless_run.after(count_production),
+            ),
+        )
+        .run();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn drive_headless_run(
+    mut run: ResMut<HeadlessRun>,
+    time: Res<Time>,
+    recipes: Res<Recipes>,
+    registry: Res<ItemRegistry>,
+    technologies: Res<Technologies>,
+    structures: Query<(), With<Structure>>,
+    mut counts: ResMut<ProductionCounts>,
+    mut time_update: ResMut<TimeUpdateStrategy>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut load_events: EventWriter<LoadGameEvent>,
+    mut exit: EventWriter<AppExit>,
+) {
+    match *run {
+        HeadlessRun::Loading { ref save, ticks } => {
+            // The save refers to recipes and items by id, so wait for the data files
+            let loaded = !recipes.0.is_empty()
+                && !registry.definitions.is_empty()
+                && !technologies.0.is_empty();
+            if !loaded {
+                if time.elapsed_seconds() > LOAD_TIMEOUT_SECONDS {
+                    println!("Timed out waiting for the data files to load");
+                    exit.send(AppExit);
+                }
+                return;
+            }
+            load_events.send(LoadGameEvent(save.clone()));
+            next_state.set(GameState::Playing);
+            // Each frame then runs exactly one simulation tick
+            *time_update = TimeUpdateStrategy::ManualDuration(tick_duration());
+            *run = HeadlessRun::Starting { ticks };
+        }
+        HeadlessRun::Starting { ticks } => {
+            // Counting starts once the loaded structures exist, so drop the tick the save
+            // was loaded in
+            counts.0.clear();
+            println!("Loaded {} structures", structures.iter().count());
+            *run = HeadlessRun::Simulating {
+                ticks,
+                remaining: ticks,
+            };
+        }
+        HeadlessRun::Simulating {
+            ticks,
+            ref mut remaining,
+        } => {
+            *remaining -= 1;
+            if *remaining == 0 {
+                report_production(ticks, &counts, &registry);
+                exit.send(AppExit);
+            }
+        }
+    }
+}
+
+fn count_production(
+    mut crafts: EventReader<CraftCompleteEvent>,
+    mut spawns: EventReader<ItemSpawnEvent>,
+    mut counts: ResMut<ProductionCounts>,
+) {
+    let crafted = crafts.iter().flat_map(|event| event.recipe.output.iter());
+    let gathered = spawns.iter().flat_map(|event| event.items.iter());
+    for item_amount in crafted.chain(gathered) {
+        *counts.0.entry(item_amount.item).or_insert(0) += item_amount.amount.unwrap_or(0);
+    }
+}
+
+fn report_production(ticks: u64, counts: &ProductionCounts, registry: &ItemRegistry) {
+    let minutes = ticks as f64 / TICKS_PER_SECOND as f64 / 60.0;
+    println!("Production after {:.1} simulated minutes:", minutes);
+    let mut counts: Vec<(&ItemType, &u32)> = counts.0.iter().collect();
+    counts.sort_by_key(|(item, _)| item.id());
+    if counts.is_empty() {
+        println!("  nothing");
+    }
+    for (item, count) in counts {
+        println!(
+            "  {}: {} ({:.2}/min)",
+            registry.name(item),
+            count,
+            *count as f64 / minutes
+        );
+    }
+}
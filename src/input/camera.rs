@@ -1,5 +1,7 @@
 use bevy::{prelude::*, window::PrimaryWindow};
 
+use crate::map::grid::{GridPosition, HoveredGrid};
+
 use super::MyWorldCoords;
 
 pub struct FaeCameraPlugin;
@@ -12,7 +14,11 @@ impl Plugin for FaeCameraPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(MyWorldCoords(Vec2::new(0.0, 0.0)))
             .add_systems(Startup, setup)
-            .add_systems(PreUpdate, my_cursor_system);
+            .add_systems(
+                PreUpdate,
+                // Ordered to ensure that we're using this frame's mouse position
+                (my_cursor_system, update_mouse_grid_location).chain(),
+            );
     }
 }
 
@@ -22,7 +28,7 @@ fn setup(mut commands: Commands) {
     commands.spawn((camera, MainCamera {}));
 }
 
-pub(crate) fn my_cursor_system(
+fn my_cursor_system(
     mut mycoords: ResMut<MyWorldCoords>,
     // query to get the window (so we can read the current cursor position)
     q_window: Query<&Window, With<PrimaryWindow>>,
@@ -46,3 +52,10 @@ pub(crate) fn my_cursor_system(
         mycoords.0 = world_position;
     }
 }
+
+fn update_mouse_grid_location(mycoords: Res<MyWorldCoords>, mut hovered_grid: ResMut<HoveredGrid>) {
+    let new_grid = GridPosition::from_position(mycoords.0);
+    if new_grid != hovered_grid.0 {
+        hovered_grid.0 = new_grid;
+    }
+}
@@ -1,9 +1,9 @@
 use bevy::{prelude::*, utils::HashMap};
 
 use crate::{
-    common::{Held, Holdable},
-    items::ItemType,
+    common::{text_input_unfocused, GameState, Held, HeldFacing, Holdable},
     player::events::PlayerMoveEvent,
+    research::AvailableStructures,
     structures::StructureType,
 };
 
@@ -11,8 +11,13 @@ pub(super) struct FaeKeyboardPlugin;
 
 impl Plugin for FaeKeyboardPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (handle_movement_input, select_held))
-            .insert_resource(HeldState::default());
+        app.add_systems(
+            Update,
+            (handle_movement_input, select_held, rotate_held)
+                .run_if(text_input_unfocused)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .insert_resource(HeldState::default());
     }
 }
 
@@ -26,7 +31,7 @@ pub(super) fn handle_movement_input(
     key_input: Res<Input<KeyCode>>,
     mut event: EventWriter<PlayerMoveEvent>,
 ) {
-    let keys = vec![
+    let keys = [
         (KeyCode::W, Vec2::new(0.0, 1.0)),
         (KeyCode::S, Vec2::new(0.0, -1.0)),
         (KeyCode::D, Vec2::new(1.0, 0.0)),
@@ -41,20 +46,30 @@ pub(super) fn handle_movement_input(
         })
         .fold(Vec2::new(0.0, 0.0), |acc, direction| acc + direction)
         .try_normalize();
-    direction.map(|direction| event.send(PlayerMoveEvent(direction)));
+    if let Some(direction) = direction {
+        event.send(PlayerMoveEvent(direction));
+    }
 }
 
 pub(super) fn select_held(
     keys: Res<Input<KeyCode>>,
     mut query: Query<&mut Held>,
     mut held_state: ResMut<HeldState>,
+    available_structures: Res<AvailableStructures>,
 ) {
     // This should be defined like this for future use with player configuration
     use Holdable::*;
-    use ItemType::*;
     use StructureType::*;
     let select_keys: HashMap<KeyCode, Vec<Holdable>> = [
-        (KeyCode::Key1, vec![Structure(Assembler)]),
+        (
+            KeyCode::Key1,
+            vec![
+                Structure(Assembler),
+                Structure(Smelter),
+                Structure(Enchanter),
+                Structure(Library),
+            ],
+        ),
         (
             KeyCode::Key2,
             vec![Structure(Conveyor), Structure(Grabber), Structure(Chest)],
@@ -67,10 +82,20 @@ pub(super) fn select_held(
                 Structure(CrystalFairy),
             ],
         ),
-        (KeyCode::Key4, vec![Item(Wood), Item(Stone), Item(Crystal)]),
         (KeyCode::Key0, vec![]),
     ]
     .into_iter()
+    // Structures still locked behind research can't be picked
+    .map(|(key, holdables)| {
+        let holdables: Vec<Holdable> = holdables
+            .into_iter()
+            .filter(|holdable| match holdable {
+                Structure(structure) => available_structures.0.contains(structure),
+                _ => true,
+            })
+            .collect();
+        (key, holdables)
+    })
     .collect();
 
     let selected_key = select_keys
@@ -85,7 +110,7 @@ pub(super) fn select_held(
         Some(key_info) => HeldState {
             key: Some(*key_info.0),
             index: match held_state.key {
-                Some(key) if key == *key_info.0 => (held_state.index + 1) % key_info.1.len(),
+                Some(key) if key == *key_info.0 => (held_state.index + 1) % key_info.1.len().max(1),
                 _ => 0,
             },
         },
@@ -95,8 +120,21 @@ pub(super) fn select_held(
     let mut held = query.single_mut();
     let holdable = select_keys
         .get(&held_state.key.map_or(KeyCode::Escape, |key| key))
-        .map(|structures| structures.get(held_state.index))
-        .flatten();
+        .and_then(|structures| structures.get(held_state.index));
     *held = Held(holdable.cloned());
     println!("Held: {:?}", held);
 }
+
+pub(super) fn rotate_held(
+    keys: Res<Input<KeyCode>>,
+    query: Query<&Held>,
+    mut held_facing: ResMut<HeldFacing>,
+) {
+    if !keys.just_pressed(KeyCode::R) {
+        return;
+    }
+    // Only structures have a facing, so don't bother rotating anything else
+    if let Some(Holdable::Structure(_)) = query.single().0 {
+        held_facing.0 = held_facing.0.rotated_clockwise();
+    }
+}
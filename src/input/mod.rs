@@ -34,11 +34,7 @@ impl FaeInputModifier {
         FaeInputModifier(modifiers)
     }
 
-    pub fn check_all_pressed(&self, modifiers: &Vec<FaeEntityInputModifier>) -> bool {
-        modifiers.iter().all(|modifier| self.0.contains(&modifier))
-    }
-
-    pub fn check_only_pressed(&self, modifiers: &Vec<FaeEntityInputModifier>) -> bool {
+    pub fn check_only_pressed(&self, modifiers: &[FaeEntityInputModifier]) -> bool {
         FaeEntityInputModifier::iter().all(|modifier| match modifiers.contains(&modifier) {
             true => self.0.contains(&modifier),
             false => !self.0.contains(&modifier),
@@ -56,7 +52,7 @@ fn gather_modifiers(keys: &Res<Input<KeyCode>>) -> Vec<FaeEntityInputModifier> {
 
 impl From<&Res<'_, Input<KeyCode>>> for FaeInputModifier {
     fn from(keys: &Res<Input<KeyCode>>) -> Self {
-        FaeInputModifier::new(gather_modifiers(&keys))
+        FaeInputModifier::new(gather_modifiers(keys))
     }
 }
 #[derive(Resource, Default, Debug, Clone)]
@@ -2,8 +2,9 @@ use bevy::prelude::*;
 
 use super::FaeInputModifier;
 use crate::{
-    common::{Clickable, Held, Holdable},
-    map::grid::{GridPosition, HoveredGrid},
+    common::{Clickable, GameState, Held, HeldFacing, Holdable},
+    map::grid::{GridPosition, HoveredGrid, OccupiedTiles},
+    ui::cursor_over_ui,
 };
 
 pub struct FaeMousePlugin;
@@ -26,9 +27,10 @@ pub struct Previewed(pub Option<Holdable>);
 impl Plugin for FaeMousePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup)
-            .add_systems(Update, (handle_click, preview_held))
-            .add_event::<FaeEntityClickEvent>()
-            .add_event::<FaeEntityContextClickEvent>()
+            .add_systems(
+                Update,
+                (handle_click, preview_held).run_if(in_state(GameState::Playing)),
+            )
             .register_type::<Previewed>();
     }
 }
@@ -39,25 +41,35 @@ fn setup(mut commands: Commands) {
 
 fn get_clicked_entities(
     mouse_grid: Res<HoveredGrid>,
+    occupied_tiles: Res<OccupiedTiles>,
     sprite_query: Query<(&GridPosition, &Transform, Entity), With<Clickable>>,
 ) -> Vec<Entity> {
+    // Structures larger than one tile are only found through the tiles they occupy
+    let occupant = occupied_tiles.get(&mouse_grid.0);
     sprite_query
         .iter()
-        .filter(|(grid, _, _)| **grid == mouse_grid.0)
+        .filter(|(grid, _, entity)| **grid == mouse_grid.0 || occupant == Some(*entity))
         .map(|(_, _, entity)| entity)
         .collect()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_click(
     mouse_grid: Res<HoveredGrid>,
     mouse: Res<Input<MouseButton>>,
     keys: Res<Input<KeyCode>>,
+    occupied_tiles: Res<OccupiedTiles>,
     sprite_query: Query<(&GridPosition, &Transform, Entity), With<Clickable>>,
     mut left_click_writer: EventWriter<FaeEntityClickEvent>,
     mut right_click_writer: EventWriter<FaeEntityContextClickEvent>,
+    interactions: Query<&Interaction>,
 ) {
+    // Clicks on the ui shouldn't also build or interact with the world behind it
+    if cursor_over_ui(&interactions) {
+        return;
+    }
     if mouse.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
-        let clicked = get_clicked_entities(mouse_grid, sprite_query);
+        let clicked = get_clicked_entities(mouse_grid, occupied_tiles, sprite_query);
         println!("Clicked: {:?}", clicked);
 
         if mouse.just_pressed(MouseButton::Left) {
@@ -80,6 +92,8 @@ pub(crate) fn preview_held(
     mut commands: Commands,
     mouse_grid: Res<HoveredGrid>,
     query: Query<&Held>,
+    held_facing: Res<HeldFacing>,
+    occupied_tiles: Res<OccupiedTiles>,
     mut previewed: Query<(Entity, &mut Previewed)>,
     asset_server: Res<AssetServer>,
 ) {
@@ -90,14 +104,18 @@ pub(crate) fn preview_held(
         Ok((previewed_entity, previewed)) => (previewed_entity, previewed),
     };
     if let Ok(held) = query.get_single() {
-        let texture = match held.0 {
+        let structure = match held.0 {
             Some(Holdable::Structure(structure)) => {
                 if let Some(Holdable::Structure(shown_type)) = previewed.0 {
-                    if shown_type == structure && !mouse_grid.is_changed() {
+                    if shown_type == structure
+                        && !mouse_grid.is_changed()
+                        && !held_facing.is_changed()
+                        && !occupied_tiles.is_changed()
+                    {
                         return;
                     }
                 }
-                Some(structure.asset_file())
+                Some(structure)
             }
             _ => None,
         };
@@ -106,16 +124,28 @@ pub(crate) fn preview_held(
         println!("Previewing: {:?}, {:?}", held.0, mouse_grid.is_changed());
 
         let held = held.0;
-        if let Some(texture) = texture {
+        if let Some(structure) = structure {
+            // Tint the ghost red when it would overlap something already placed
+            let blocked =
+                !occupied_tiles.is_free(&structure.occupied_tiles(&mouse_grid.0, held_facing.0));
             commands.spawn((
                 SpriteBundle {
                     sprite: Sprite {
-                        color: Color::rgba(1.0, 1.0, 1.0, 0.5),
+                        color: match blocked {
+                            true => Color::rgba(1.0, 0.3, 0.3, 0.5),
+                            false => Color::rgba(1.0, 1.0, 1.0, 0.5),
+                        },
+                        custom_size: Some(structure.sprite_size()),
                         ..default()
                     },
-                    texture: asset_server.load(texture),
+                    texture: asset_server.load(structure.asset_file()),
                     transform: Transform {
-                        translation: mouse_grid.0.sprite_translation_z(1.0),
+                        translation: structure.sprite_translation_z(
+                            &mouse_grid.0,
+                            held_facing.0,
+                            1.0,
+                        ),
+                        rotation: held_facing.0.rotation(),
                         ..default()
                     },
                     ..default()
@@ -0,0 +1,112 @@
+use bevy::{
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+use super::ItemType;
+use crate::data::{loaded_definitions, DataFolderPlugin, DataSet};
+
+const DEFAULT_STACK_SIZE: u32 = 50;
+
+pub struct ItemDefinitionPlugin;
+
+impl Plugin for ItemDefinitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(DataFolderPlugin::<ItemDefinitionSet>::default())
+            .insert_resource(ItemRegistry::default())
+            .add_systems(
+                Update,
+                update_item_registry.run_if(on_event::<AssetEvent<ItemDefinitionSet>>()),
+            )
+            .register_type::<ItemCategory>();
+    }
+}
+
+// Inventories list items grouped in this order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Reflect, Deserialize)]
+pub enum ItemCategory {
+    Resource,
+    Intermediate,
+    Product,
+}
+
+/// Everything the game knows about an item beyond its id.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ItemDefinition {
+    pub id: ItemType,
+    pub name: String,
+    pub icon: String,
+    pub stack_size: u32,
+    pub category: ItemCategory,
+}
+
+/// Item metadata loaded from the item asset files, keyed by item.
+#[derive(Resource, Default, Debug)]
+pub struct ItemRegistry {
+    pub definitions: HashMap<ItemType, ItemDefinition>,
+    pub icons: HashMap<ItemType, Handle<Image>>,
+}
+
+impl ItemRegistry {
+    pub fn get(&self, item: &ItemType) -> Option<&ItemDefinition> {
+        self.definitions.get(item)
+    }
+
+    /// The display name, falling back to the id for items missing a definition.
+    pub fn name(&self, item: &ItemType) -> String {
+        self.get(item)
+            .map_or_else(|| item.to_string(), |definition| definition.name.clone())
+    }
+
+    pub fn category(&self, item: &ItemType) -> Option<ItemCategory> {
+        self.get(item).map(|definition| definition.category)
+    }
+
+    pub fn icon(&self, item: &ItemType) -> Option<Handle<Image>> {
+        self.icons.get(item).cloned()
+    }
+
+    /// How many of an item fit in a single inventory slot.
+    pub fn stack_size(&self, item: &ItemType) -> u32 {
+        self.get(item)
+            .map_or(DEFAULT_STACK_SIZE, |definition| definition.stack_size)
+            .max(1)
+    }
+}
+
+/// A single asset file of item definitions.
+#[derive(Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "a3e1c7d2-6b4f-4c8a-9d2e-1f5b7c3a8e64"]
+pub struct ItemDefinitionSet {
+    pub items: Vec<ItemDefinition>,
+}
+
+impl DataSet for ItemDefinitionSet {
+    type Definition = ItemDefinition;
+
+    const FOLDER: &'static str = "items";
+    const EXTENSIONS: &'static [&'static str] = &["items.ron"];
+
+    fn definitions(&self) -> &[ItemDefinition] {
+        &self.items
+    }
+}
+
+fn update_item_registry(
+    item_sets: Res<Assets<ItemDefinitionSet>>,
+    asset_server: Res<AssetServer>,
+    mut registry: ResMut<ItemRegistry>,
+) {
+    registry.definitions = loaded_definitions(&item_sets)
+        .map(|definition| (definition.id, definition))
+        .collect();
+    let icons = registry
+        .definitions
+        .values()
+        .map(|definition| (definition.id, asset_server.load(definition.icon.as_str())))
+        .collect();
+    registry.icons = icons;
+    println!("Loaded {} items", registry.definitions.len());
+}
@@ -2,7 +2,11 @@ use bevy::{prelude::*, utils::HashMap};
 
 use crate::recipes::Recipe;
 
-use super::ItemType;
+use super::{definitions::ItemRegistry, ItemType};
+
+// Crafters and libraries only take in inputs for this many uses, so their slots can't all
+// fill up with one input and leave the rest, or the finished output, nowhere to go
+const BUFFERED_CRAFTS: u32 = 3;
 
 #[derive(Component, Debug, Reflect, Clone)]
 pub struct Inventory {
@@ -30,7 +34,7 @@ pub struct ItemAmount {
 }
 
 impl ItemAmount {
-    fn to_tuple(&self) -> (ItemType, u32) {
+    fn to_tuple(self) -> (ItemType, u32) {
         (self.item, self.amount.unwrap_or(0))
     }
 }
@@ -44,9 +48,9 @@ impl From<(ItemType, u32)> for ItemAmount {
     }
 }
 
-impl Into<(ItemType, u32)> for ItemAmount {
-    fn into(self) -> (ItemType, u32) {
-        (self.item, self.amount.unwrap_or(0))
+impl From<ItemAmount> for (ItemType, u32) {
+    fn from(item_amount: ItemAmount) -> Self {
+        item_amount.to_tuple()
     }
 }
 
@@ -54,6 +58,8 @@ impl Into<(ItemType, u32)> for ItemAmount {
 pub enum InventoryFilter {
     All,
     Only(Vec<ItemType>),
+    // Only these items, each up to the given amount
+    Limited(Vec<ItemAmount>),
     Except(Vec<ItemType>),
     None,
 }
@@ -74,16 +80,8 @@ impl Inventory {
     }
 
     pub fn filtered_for(&mut self, recipe: Option<&Recipe>) -> &Self {
-        let inputs: Option<Vec<ItemType>> = recipe.map(|recipe| {
-            recipe
-                .input
-                .iter()
-                .map(|item_amount| item_amount.item)
-                .collect()
-        });
-        if let Some(inputs) = inputs {
-            self.input_filter = InventoryFilter::Only(inputs.clone());
-            self.output_filter = InventoryFilter::Except(inputs);
+        if let Some(recipe) = recipe {
+            self.filtered_for_inputs(&recipe.input);
         } else {
             // Nothing can go in without a recipe
             self.input_filter = InventoryFilter::None;
@@ -92,6 +90,21 @@ impl Inventory {
         self
     }
 
+    /// Takes in only the inputs, enough for a few uses, and keeps them from being pulled out.
+    pub fn filtered_for_inputs(&mut self, input: &[ItemAmount]) -> &Self {
+        let limits = input
+            .iter()
+            .map(|item_amount| {
+                let (item, amount) = item_amount.to_tuple();
+                (item, amount * BUFFERED_CRAFTS).into()
+            })
+            .collect();
+        let inputs = input.iter().map(|item_amount| item_amount.item).collect();
+        self.input_filter = InventoryFilter::Limited(limits);
+        self.output_filter = InventoryFilter::Except(inputs);
+        self
+    }
+
     pub fn clear_filters(mut self) -> Self {
         self.input_filter = InventoryFilter::All;
         self.output_filter = InventoryFilter::All;
@@ -104,29 +117,83 @@ impl Inventory {
         self
     }
 
-    pub fn has_items(&self, items: &Vec<ItemAmount>) -> bool {
+    pub fn has_items(&self, items: &[ItemAmount]) -> bool {
         items.iter().all(|item_amount| self.has_item(item_amount))
     }
 
     pub fn has_item(&self, item_amount: &ItemAmount) -> bool {
         self.items
             .get(&item_amount.item)
-            .map_or(false, |a| *a >= item_amount.amount.map_or(0, |a| a))
+            .is_some_and(|a| *a >= item_amount.amount.unwrap_or(0))
     }
 
-    pub fn add_items(&mut self, items: &Vec<ItemAmount>) {
-        items.iter().for_each(|item_amount| {
-            let (item, amount) = item_amount.to_tuple();
-            if let Some(current) = self.items.get_mut(&item) {
-                *current += amount;
-            } else {
-                self.items.insert(item.clone(), amount);
-            }
-        });
-        println!("{:?}", self.items);
+    /// Number of slots in use, with each slot holding up to one stack of a single item.
+    pub fn used_slots(&self, registry: &ItemRegistry) -> u32 {
+        self.items
+            .iter()
+            .map(|(item, amount)| amount.div_ceil(registry.stack_size(item)))
+            .sum()
     }
 
-    pub fn remove_items(&mut self, items: &Vec<ItemAmount>) -> bool {
+    /// The contents split into one stack per used slot, in a stable order.
+    pub fn stacks(&self, registry: &ItemRegistry) -> Vec<ItemAmount> {
+        let mut items: Vec<(&ItemType, &u32)> = self.items.iter().collect();
+        items.sort_by_key(|(item, _)| (registry.category(item), item.id()));
+        items
+            .into_iter()
+            .flat_map(|(item, amount)| {
+                let stack = registry.stack_size(item);
+                (0..amount.div_ceil(stack))
+                    .map(move |index| (*item, (amount - index * stack).min(stack)).into())
+            })
+            .collect()
+    }
+
+    /// How many more of an item fit, topping up its partial stack before using free slots.
+    pub fn space_for(&self, item: &ItemType, registry: &ItemRegistry) -> u32 {
+        let stack = registry.stack_size(item);
+        let amount = self.items.get(item).copied().unwrap_or(0);
+        let partial_stack_space = (stack - amount % stack) % stack;
+        let free_slots = (self.slots as u32).saturating_sub(self.used_slots(registry));
+        partial_stack_space + free_slots * stack
+    }
+
+    pub fn has_space_for(&self, items: &[ItemAmount], registry: &ItemRegistry) -> bool {
+        // Fill a copy so items competing for the same free slots are accounted for
+        let mut inventory = self.clone();
+        let accepted: u32 = inventory
+            .insert_items(items, registry)
+            .iter()
+            .map(|item_amount| item_amount.amount.unwrap_or(0))
+            .sum();
+        let requested: u32 = items
+            .iter()
+            .map(|item_amount| item_amount.amount.unwrap_or(0))
+            .sum();
+        accepted == requested
+    }
+
+    /// Adds as much of the items as fits and returns the amounts that were accepted.
+    pub fn add_items(&mut self, items: &[ItemAmount], registry: &ItemRegistry) -> Vec<ItemAmount> {
+        self.insert_items(items, registry)
+    }
+
+    fn insert_items(&mut self, items: &[ItemAmount], registry: &ItemRegistry) -> Vec<ItemAmount> {
+        items
+            .iter()
+            .filter_map(|item_amount| {
+                let (item, amount) = item_amount.to_tuple();
+                let accepted = amount.min(self.space_for(&item, registry));
+                if accepted == 0 {
+                    return None;
+                }
+                *self.items.entry(item).or_insert(0) += accepted;
+                Some((item, accepted).into())
+            })
+            .collect()
+    }
+
+    pub fn remove_items(&mut self, items: &[ItemAmount]) -> bool {
         if self.has_items(items) {
             items.iter().for_each(|item_amount| {
                 let (item, amount) = item_amount.to_tuple();
@@ -142,10 +209,10 @@ impl Inventory {
             println!("{:?}", self.items);
             return true;
         }
-        return false;
+        false
     }
 
-    pub fn remove_if_possible(&mut self, items: &Vec<ItemAmount>) -> Vec<ItemAmount> {
+    pub fn remove_if_possible(&mut self, items: &[ItemAmount]) -> Vec<ItemAmount> {
         items
             .iter()
             .filter_map(|item_amount| {
@@ -169,14 +236,21 @@ impl Inventory {
             .collect()
     }
 
-    pub fn can_add_items(&self, items: &[ItemType]) -> bool {
+    pub fn can_add_items(&self, items: &[ItemType], registry: &ItemRegistry) -> bool {
         use InventoryFilter::*;
-        match &self.input_filter {
+        let allowed = match &self.input_filter {
             All => true,
             Only(allowed) => items.iter().all(|item| allowed.contains(item)),
+            Limited(limits) => items.iter().all(|item| {
+                let held = self.items.get(item).copied().unwrap_or(0);
+                limits
+                    .iter()
+                    .any(|limit| limit.item == *item && held < limit.amount.unwrap_or(0))
+            }),
             Except(disallowed) => items.iter().all(|item| !disallowed.contains(item)),
             None => false,
-        }
+        };
+        allowed && items.iter().all(|item| self.space_for(item, registry) > 0)
     }
 
     pub fn pullable_items(&self, items: Vec<ItemAmount>) -> Vec<ItemAmount> {
@@ -186,6 +260,10 @@ impl Inventory {
                 .into_iter()
                 .filter(|item| allowed.contains(&item.item))
                 .collect(),
+            InventoryFilter::Limited(allowed) => items
+                .into_iter()
+                .filter(|item| allowed.iter().any(|limit| limit.item == item.item))
+                .collect(),
             InventoryFilter::Except(disallowed) => items
                 .into_iter()
                 .filter(|item| !disallowed.contains(&item.item))
@@ -194,7 +272,7 @@ impl Inventory {
         }
     }
 
-    pub fn try_empty_into_other(&mut self, other: &mut Inventory) {
+    pub fn try_empty_into_other(&mut self, other: &mut Inventory, registry: &ItemRegistry) {
         // Empty, respecting filters
         let items_to_pull = self.pullable_items(
             self.items
@@ -202,38 +280,112 @@ impl Inventory {
                 .map(|(item, amount)| (*item, *amount).into())
                 .collect(),
         );
-        self.move_items_into_other(other, items_to_pull);
+        self.move_items_into_other(other, items_to_pull, registry);
     }
 
-    pub fn force_empty_into_other(&mut self, other: &mut Inventory) -> &mut Self {
+    pub fn force_empty_into_other(
+        &mut self,
+        other: &mut Inventory,
+        registry: &ItemRegistry,
+    ) -> &mut Self {
         // Skip filtering
         let items_to_pull = self
             .items
             .iter()
             .map(|(item, amount)| (*item, *amount).into())
             .collect();
-        self.move_items_into_other(other, items_to_pull);
+        self.move_items_into_other(other, items_to_pull, registry);
         self
     }
 
-    pub fn move_items_into_other(&mut self, other: &mut Inventory, items_to_pull: Vec<ItemAmount>) {
-        // Generic function to move items between inventories by type
-        let pulled_types: Vec<ItemType> = items_to_pull
+    pub fn move_items_into_other(
+        &mut self,
+        other: &mut Inventory,
+        items_to_pull: Vec<ItemAmount>,
+        registry: &ItemRegistry,
+    ) {
+        // Generic function to move items between inventories by type.
+        // Whatever doesn't fit in the other inventory stays here.
+        let available: Vec<ItemAmount> = items_to_pull
             .iter()
-            .map(|item_amount| item_amount.item)
+            .filter_map(|item_amount| {
+                let held = self.items.get(&item_amount.item).copied().unwrap_or(0);
+                let amount = item_amount.amount.map_or(held, |amount| amount.min(held));
+                (amount > 0).then(|| (item_amount.item, amount).into())
+            })
             .collect();
+        let accepted = other.add_items(&available, registry);
+        self.remove_items(&accepted);
+    }
+}
 
-        other.add_items(
-            &self
-                .items
-                .iter()
-                .filter(|(item, _)| pulled_types.contains(item))
-                .map(|(item, amount)| (*item, *amount).into())
-                .collect(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::items::definitions::{ItemCategory, ItemDefinition};
+
+    // Wood stacks to 10, everything else to the default of 50
+    fn registry() -> ItemRegistry {
+        let mut registry = ItemRegistry::default();
+        registry.definitions.insert(
+            ItemType::WOOD,
+            ItemDefinition {
+                id: ItemType::WOOD,
+                name: "Wood".to_string(),
+                icon: String::new(),
+                stack_size: 10,
+                category: ItemCategory::Resource,
+            },
         );
-        items_to_pull.into_iter().for_each(|item| {
-            self.items.remove(&item.item);
-        });
-        println!("{:?}", self.items)
+        registry
+    }
+
+    #[test]
+    fn space_tops_up_partial_stacks_before_free_slots() {
+        let registry = registry();
+        let inventory = Inventory::new(3, vec![(ItemType::WOOD, 13).into()]);
+
+        assert_eq!(inventory.used_slots(&registry), 2);
+        assert_eq!(inventory.space_for(&ItemType::WOOD, &registry), 17);
+        assert_eq!(inventory.space_for(&ItemType::STONE, &registry), 50);
+    }
+
+    #[test]
+    fn full_slots_take_nothing_new() {
+        let registry = registry();
+        let inventory = Inventory::new(2, vec![(ItemType::WOOD, 20).into()]);
+
+        assert_eq!(inventory.space_for(&ItemType::WOOD, &registry), 0);
+        assert!(!inventory.can_add_items(&[ItemType::WOOD], &registry));
+        assert!(!inventory.can_add_items(&[ItemType::STONE], &registry));
+    }
+
+    #[test]
+    fn items_compete_for_the_same_free_slots() {
+        let registry = registry();
+        let inventory = Inventory::new(1, vec![]);
+
+        assert!(inventory.has_space_for(&[(ItemType::WOOD, 10).into()], &registry));
+        assert!(!inventory.has_space_for(&[(ItemType::WOOD, 11).into()], &registry));
+        assert!(!inventory.has_space_for(
+            &[(ItemType::WOOD, 1).into(), (ItemType::STONE, 1).into()],
+            &registry
+        ));
+    }
+
+    #[test]
+    fn inputs_are_limited_to_a_few_uses() {
+        let registry = registry();
+        let mut inventory = Inventory::new(4, vec![(ItemType::WOOD, 5).into()]);
+        inventory.filtered_for_inputs(&[(ItemType::WOOD, 2).into()]);
+
+        assert!(inventory.can_add_items(&[ItemType::WOOD], &registry));
+        assert!(!inventory.can_add_items(&[ItemType::STONE], &registry));
+        inventory.add_items(&[(ItemType::WOOD, 1).into()], &registry);
+        assert!(!inventory.can_add_items(&[ItemType::WOOD], &registry));
+        // Inputs can't be pulled back out
+        assert!(inventory
+            .pullable_items(vec![(ItemType::WOOD, 6).into()])
+            .is_empty());
     }
 }
@@ -1,10 +1,14 @@
-use std::{arch::x86_64::_mm256_add_epi16, thread::spawn};
-
 use bevy::prelude::*;
 
-use crate::structures::StructureType;
+use crate::{
+    common::{GameState, Paused},
+    map::{grid::GridPosition, resource_nodes::ResourceNodes},
+    simulation::{TickTimer, NORMAL_SPEED},
+    structures::StructureType,
+};
 
 use super::{
+    definitions::ItemRegistry,
     inventory::{Inventory, ItemAmount},
     ItemType,
 };
@@ -13,17 +17,20 @@ pub struct ItemSpawnerPlugin;
 
 impl Plugin for ItemSpawnerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_item_into_inventory)
-            .add_event::<ItemSpawnEvent>()
-            .register_type::<ItemSpawner>();
+        app.add_systems(
+            FixedUpdate,
+            spawn_item_into_inventory.run_if(in_state(GameState::Playing)),
+        )
+        .add_event::<ItemSpawnEvent>()
+        .register_type::<ItemSpawner>()
+        .register_type::<ItemSpawnSource>();
     }
 }
 
-#[derive(Component, Debug, Reflect, Default)]
+#[derive(Component, Debug, Reflect, Default, Clone)]
 pub struct ItemSpawner {
     pub output: Vec<ItemAmount>,
-    pub interval_seconds: f32,
-    pub timer: Timer,
+    pub timer: TickTimer,
 }
 
 impl TryFrom<StructureType> for ItemSpawner {
@@ -31,9 +38,9 @@ impl TryFrom<StructureType> for ItemSpawner {
     fn try_from(value: StructureType) -> Result<Self, Self::Error> {
         use StructureType::{CrystalFairy, StoneFairy, WoodFairy};
         match value {
-            WoodFairy => Ok(ItemSpawner::new(vec![(ItemType::Wood, 1).into()], 10.0)),
-            StoneFairy => Ok(ItemSpawner::new(vec![(ItemType::Stone, 1).into()], 10.0)),
-            CrystalFairy => Ok(ItemSpawner::new(vec![(ItemType::Crystal, 1).into()], 10.0)),
+            WoodFairy => Ok(ItemSpawner::new(vec![(ItemType::WOOD, 1).into()], 10.0)),
+            StoneFairy => Ok(ItemSpawner::new(vec![(ItemType::STONE, 1).into()], 10.0)),
+            CrystalFairy => Ok(ItemSpawner::new(vec![(ItemType::CRYSTAL, 1).into()], 10.0)),
             _ => Err(()),
         }
     }
@@ -45,47 +52,71 @@ pub struct ItemSpawnEvent {
     pub items: Vec<ItemAmount>,
 }
 
-#[derive(Component, Debug, Reflect)]
-pub struct ItemSpawnSource(Inventory);
+/// The resource tiles a spawner draws its output from, e.g. the forest around a gatherer.
+/// What is left in them is tracked by the map's `ResourceNodes`.
+#[derive(Component, Debug, Reflect, Default, Clone)]
+pub struct ItemSpawnSource {
+    pub tiles: Vec<GridPosition>,
+}
+
+impl ItemSpawnSource {
+    pub fn is_depleted(&self, nodes: &ResourceNodes) -> bool {
+        nodes.total(&self.tiles) == 0
+    }
+}
 
+/// Gathering speed as a percentage, 100 being normal.
 #[derive(Component, Debug, Reflect, Clone, Copy)]
-pub struct ItemSpawnSpeed(pub f32);
+pub struct ItemSpawnSpeed(pub u32);
 
 impl ItemSpawner {
     pub fn new(output: Vec<ItemAmount>, interval_seconds: f32) -> Self {
         ItemSpawner {
             output,
-            interval_seconds,
-            timer: Timer::from_seconds(interval_seconds, TimerMode::Repeating),
+            timer: TickTimer::from_seconds(interval_seconds),
         }
     }
 }
 
-fn spawn_item_into_inventory(
-    mut spawners: Query<(
-        Entity,
-        &mut ItemSpawner,
-        Option<&ItemSpawnSpeed>,
-        Option<&mut ItemSpawnSource>,
-        &mut Inventory,
-    )>,
+type SpawnerComponents = (
+    Entity,
+    &'static mut ItemSpawner,
+    Option<&'static ItemSpawnSpeed>,
+    Option<&'static ItemSpawnSource>,
+    &'static mut Inventory,
+);
+
+pub fn spawn_item_into_inventory(
+    mut spawners: Query<SpawnerComponents, Without<Paused>>,
+    mut nodes: ResMut<ResourceNodes>,
     mut event: EventWriter<ItemSpawnEvent>,
-    time: Res<Time>,
+    registry: Res<ItemRegistry>,
 ) {
     for (entity, mut spawner, speed, source, mut inventory) in spawners.iter_mut() {
-        let spawn_speed = speed.map_or(1.0, |s| s.0);
-        if spawner
-            .timer
-            .tick(time.delta().mul_f32(spawn_speed))
-            .finished()
-        {
-            let items_to_add = match source {
-                Some(mut source_inventory) => {
-                    source_inventory.0.remove_if_possible(&spawner.output)
-                }
+        let spawn_speed = speed.map_or(NORMAL_SPEED, |s| s.0);
+        if !inventory.has_space_for(&spawner.output, &registry) {
+            // Leave the items in the source until there is room, then gather right away
+            spawner.timer.tick_held(spawn_speed);
+            continue;
+        }
+        if spawner.timer.tick(spawn_speed) {
+            let items_to_add: Vec<ItemAmount> = match source {
+                Some(source) => spawner
+                    .output
+                    .iter()
+                    .map(|output| {
+                        let taken = nodes.take(&source.tiles, output.amount.unwrap_or(0));
+                        (output.item, taken).into()
+                    })
+                    .filter(|taken: &ItemAmount| taken.amount.unwrap_or(0) > 0)
+                    .collect(),
                 None => spawner.output.clone(),
             };
-            inventory.add_items(&items_to_add);
+            if items_to_add.is_empty() {
+                // The source is depleted, so there is nothing to gather
+                continue;
+            }
+            inventory.add_items(&items_to_add, &registry);
             event.send(ItemSpawnEvent {
                 entity,
                 items: items_to_add,
@@ -1,22 +1,24 @@
 // TODO: Determine how to handle the organization of this bit.
 
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashSet};
 use core::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::{Mutex, OnceLock};
 
 use crate::{
-    common::{Clickable, Held, Holdable},
-    input::{
-        mouse::{FaeEntityClickEvent, FaeEntityContextClickEvent},
-        FaeEntityInputModifier, FaeInputModifier,
-    },
+    common::{Clickable, GameState, Held, Holdable},
+    input::{mouse::FaeEntityClickEvent, FaeEntityInputModifier},
     player::Player,
+    structures::{accepts_delivery, deliver, DeliveryTarget},
 };
 
 use self::{
+    definitions::{ItemDefinitionPlugin, ItemRegistry},
     inventory::{Inventory, ItemAmount},
     item_spawner::ItemSpawnerPlugin,
 };
 
+pub(crate) mod definitions;
 pub(crate) mod inventory;
 pub(crate) mod item_spawner;
 
@@ -24,8 +26,11 @@ pub struct ItemPlugin;
 
 impl Plugin for ItemPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ItemSpawnerPlugin)
-            .add_systems(Update, (handle_click_insert_item, handle_click_empty))
+        app.add_plugins((ItemSpawnerPlugin, ItemDefinitionPlugin))
+            .add_systems(
+                Update,
+                (handle_click_insert_item, handle_click_empty).run_if(in_state(GameState::Playing)),
+            )
             .register_type::<Item>()
             .register_type::<Inventory>()
             .register_type::<ItemType>();
@@ -36,43 +41,77 @@ impl Plugin for ItemPlugin {
 #[derive(Component, Reflect)]
 pub struct Item(ItemType);
 
+/// Identifies an item by its id from the item definition files, e.g. `core::wood`.
+/// Ids are interned so the type stays `Copy` and cheap to use as a key.
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
-pub enum ItemType {
-    Wood,
-    Crystal,
-    Stone,
-    Toy,
+#[reflect_value(PartialEq, Hash, Debug, Serialize, Deserialize)]
+pub struct ItemType(&'static str);
+
+impl ItemType {
+    // Items the game itself refers to, everything else only exists in the data files
+    pub const WOOD: ItemType = ItemType("core::wood");
+    pub const CRYSTAL: ItemType = ItemType("core::crystal");
+    pub const STONE: ItemType = ItemType("core::stone");
+    pub const TOY: ItemType = ItemType("core::toy");
+    pub const PLANK: ItemType = ItemType("core::plank");
+    pub const STONE_BRICK: ItemType = ItemType("core::stone-brick");
+    pub const POLISHED_CRYSTAL: ItemType = ItemType("core::polished-crystal");
+    pub const FAE_DUST: ItemType = ItemType("core::fae-dust");
+
+    pub fn from_id(id: &str) -> Self {
+        static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+        let mut interned = INTERNED.get_or_init(Default::default).lock().unwrap();
+        match interned.get(id) {
+            Some(existing) => ItemType(existing),
+            None => {
+                let id: &'static str = Box::leak(id.to_string().into_boxed_str());
+                interned.insert(id);
+                ItemType(id)
+            }
+        }
+    }
+
+    pub fn id(&self) -> &'static str {
+        self.0
+    }
 }
 
 impl fmt::Display for ItemType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use ItemType::*;
-        match self {
-            Wood => write!(f, "wood"),
-            Crystal => write!(f, "crystal"),
-            Stone => write!(f, "stone"),
-            Toy => write!(f, "toy"),
-        }
+        // Leave off the namespace, the item definitions hold the proper display name
+        write!(f, "{}", self.0.rsplit("::").next().unwrap_or(self.0))
+    }
+}
+
+impl Serialize for ItemType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for ItemType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|id| ItemType::from_id(&id))
     }
 }
 
 fn handle_click_insert_item(
     mut event: EventReader<FaeEntityClickEvent>,
     mut player: Query<(&mut Inventory, &Transform), With<Player>>,
-    mut query: Query<(&mut Inventory, &Transform), (With<Clickable>, Without<Player>)>,
+    mut query: Query<DeliveryTarget, (With<Clickable>, Without<Player>)>,
     mut held_item: Query<&mut Held>,
+    registry: Res<ItemRegistry>,
 ) {
-    // Retrieve the newest click event, if it exists, and extract the clicked inventory and transform.
-    let (mut clicked_inventory, _clicked_transform) = match event.iter().last() {
+    // Retrieve the newest click event, if it exists, and extract the clicked inventory.
+    let (mut clicked_inventory, clicked_burner) = match event.iter().last() {
         Some(click_event) => {
-            if !click_event.modifiers.check_only_pressed(&vec![]) || click_event.entities.is_empty()
-            {
+            if !click_event.modifiers.check_only_pressed(&[]) || click_event.entities.is_empty() {
                 return;
             }
 
             let entity = click_event.entities.first().unwrap();
-            if let Ok((inventory, transform)) = query.get_mut(*entity) {
-                (inventory, transform)
+            if let Ok((inventory, burner)) = query.get_mut(*entity) {
+                (inventory, burner)
             } else {
                 return;
             }
@@ -86,13 +125,23 @@ fn handle_click_insert_item(
         _ => return,
     };
 
-    if !clicked_inventory.can_add_items(&[item]) {
+    if !accepts_delivery(
+        &clicked_inventory,
+        clicked_burner.as_deref(),
+        item,
+        &registry,
+    ) {
         return;
     }
 
     let (mut player_inventory, _player_transform) = player.single_mut();
-    if player_inventory.remove_items(&vec![(item, 1).into()]) {
-        clicked_inventory.add_items(&vec![(item, 1).into()]);
+    if player_inventory.remove_items(&[(item, 1).into()]) {
+        deliver(
+            &mut clicked_inventory,
+            clicked_burner.map(Mut::into_inner),
+            item,
+            &registry,
+        );
     }
 
     if !player_inventory.has_item(&ItemAmount {
@@ -103,16 +152,19 @@ fn handle_click_insert_item(
     }
 }
 
+type ClickedInventory = (With<Clickable>, Without<Player>);
+
 fn handle_click_empty(
     mut event: EventReader<FaeEntityClickEvent>,
     mut player: Query<(&mut Inventory, &Transform), With<Player>>,
-    mut query: Query<(&mut Inventory, &Transform), (With<Clickable>, Without<Player>)>,
+    mut query: Query<(&mut Inventory, &Transform), ClickedInventory>,
+    registry: Res<ItemRegistry>,
 ) {
     let (mut clicked_inventory, _clicked_transform) = match event.iter().last() {
         Some(click_event) => {
             if !click_event
                 .modifiers
-                .check_only_pressed(&vec![FaeEntityInputModifier::Ctrl])
+                .check_only_pressed(&[FaeEntityInputModifier::Ctrl])
                 || click_event.entities.is_empty()
             {
                 return;
@@ -130,5 +182,5 @@ fn handle_click_empty(
     println!("Emptying inventory to player");
 
     let (mut player_inventory, _player_transform) = player.single_mut();
-    clicked_inventory.try_empty_into_other(&mut player_inventory);
+    clicked_inventory.try_empty_into_other(&mut player_inventory, &registry);
 }
@@ -1,35 +1,69 @@
-use bevy::{input::common_conditions::input_toggle_active, prelude::*};
+use std::time::Duration;
+
+use bevy::{asset::ChangeWatcher, input::common_conditions::input_toggle_active, prelude::*};
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use common::FaeCommonPlugin;
 use crafting::CraftingPlugin;
+use headless::{run_headless, HeadlessOptions};
 use input::FaeInputPlugin;
 use items::ItemPlugin;
 use map::MapPlugin;
 use player::PlayerPlugin;
+use recipes::RecipePlugin;
 use research::ResearchPlugin;
+use save::SavePlugin;
+use simulation::SimulationPlugin;
+use statistics::StatisticsPlugin;
 use structures::StructurePlugin;
+use ui::FaeUiPlugin;
 
 mod common;
 mod crafting;
+mod data;
+mod headless;
 mod input;
 mod items;
 mod map;
 mod player;
+mod rates;
 mod recipes;
 mod research;
+mod save;
+mod simulation;
+mod statistics;
 mod structures;
+mod ui;
 
 #[derive(Component)]
 pub struct Speed(pub f32);
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    match HeadlessOptions::from_args(&args) {
+        Ok(Some(options)) => {
+            run_headless(options);
+            return;
+        }
+        Ok(None) => (),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+
     App::new()
         .add_plugins(
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())
+                // Hot reload assets so recipe files can be edited while the game runs
+                .set(AssetPlugin {
+                    watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+                    ..default()
+                })
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         title: "Fae Factory".into(),
-                        resolution: (1000.0, 750.0).into(),
+                        resolution: (1000.0_f32, 750.0_f32).into(),
                         resizable: false,
                         ..default()
                     }),
@@ -38,15 +72,20 @@ fn main() {
                 .build(),
         )
         .add_plugins((
+            FaeCommonPlugin,
+            SimulationPlugin,
             PlayerPlugin,
             CraftingPlugin,
+            RecipePlugin,
             ItemPlugin,
             StructurePlugin,
             FaeInputPlugin,
             ResearchPlugin,
             MapPlugin,
+            SavePlugin,
+            StatisticsPlugin,
+            FaeUiPlugin,
         ))
-        .add_systems(Update, bevy::window::close_on_esc)
         .add_plugins(
             WorldInspectorPlugin::default().run_if(input_toggle_active(false, KeyCode::Grave)),
         )
@@ -0,0 +1,181 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{common::GameState, player::Player};
+
+use super::grid::{Chunk, GridPosition, GridTile, TileType};
+
+// Chunks within this many chunks of the player are spawned
+const CHUNK_LOAD_RADIUS: i32 = 2;
+// Chunks further than this are despawned, leaving a margin so walking a chunk edge doesn't thrash
+const CHUNK_UNLOAD_RADIUS: i32 = 3;
+// Tiles around the origin that are kept dry so the player always starts on land
+const SPAWN_CLEARING_RADIUS: i32 = 4;
+const TILE_Z: f32 = -1.0;
+const DEFAULT_SEED: u64 = 0xFAE_FAC7;
+
+pub struct WorldGenerationPlugin;
+
+impl Plugin for WorldGenerationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WorldGenerator::from_env())
+            .insert_resource(LoadedChunks::default())
+            .add_systems(
+                Update,
+                (load_nearby_chunks, unload_distant_chunks).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Deterministically decides the tile type of every grid position from a seed.
+#[derive(Resource, Reflect, Debug, Clone, Copy)]
+pub struct WorldGenerator {
+    pub seed: u64,
+}
+
+impl Default for WorldGenerator {
+    fn default() -> Self {
+        WorldGenerator { seed: DEFAULT_SEED }
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct LoadedChunks(pub HashMap<Chunk, Entity>);
+
+impl WorldGenerator {
+    pub fn new(seed: u64) -> Self {
+        WorldGenerator { seed }
+    }
+
+    /// Uses `FAE_SEED` when set so layouts can be tested on the same map.
+    pub fn from_env() -> Self {
+        std::env::var("FAE_SEED")
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+            .map_or_else(WorldGenerator::default, WorldGenerator::new)
+    }
+
+    pub fn tile_at(&self, position: &GridPosition) -> TileType {
+        use TileType::*;
+        let position = position.0;
+        let in_clearing = position.abs().max_element() <= SPAWN_CLEARING_RADIUS;
+        if !in_clearing && self.value_noise(position, 12, 0) < 0.25 {
+            Water
+        } else if self.value_noise(position, 6, 1) > 0.75 {
+            Forest
+        } else if self.value_noise(position, 5, 2) > 0.78 {
+            Stone
+        } else if self.value_noise(position, 4, 3) > 0.82 {
+            Crystal
+        } else {
+            Grass
+        }
+    }
+
+    // A random value in [0, 1) for each lattice point of a channel
+    fn lattice_value(&self, x: i32, y: i32, channel: u64) -> f32 {
+        // splitmix64 finalizer over the combined coordinates
+        let mut hash = self.seed
+            ^ channel.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (x as u32 as u64).wrapping_mul(0xBF58_476D_1CE4_E5B9)
+            ^ (y as u32 as u64).wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash ^= hash >> 30;
+        hash = hash.wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        hash ^= hash >> 27;
+        hash = hash.wrapping_mul(0x94D0_49BB_1331_11EB);
+        hash ^= hash >> 31;
+        (hash >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Smoothly interpolated value noise with features roughly `scale` tiles wide
+    fn value_noise(&self, position: IVec2, scale: i32, channel: u64) -> f32 {
+        let cell = IVec2::new(position.x.div_euclid(scale), position.y.div_euclid(scale));
+        let t = (position - cell * scale).as_vec2() / scale as f32;
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (tx, ty) = (smooth(t.x), smooth(t.y));
+        let corner = |dx: i32, dy: i32| self.lattice_value(cell.x + dx, cell.y + dy, channel);
+
+        let bottom = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * tx;
+        let top = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * tx;
+        bottom + (top - bottom) * ty
+    }
+}
+
+fn chunk_distance(a: &Chunk, b: &Chunk) -> i32 {
+    (a.0 - b.0).abs().max_element()
+}
+
+fn load_nearby_chunks(
+    mut commands: Commands,
+    generator: Res<WorldGenerator>,
+    mut loaded_chunks: ResMut<LoadedChunks>,
+    player: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+) {
+    let player_chunk = match player.get_single() {
+        Ok(grid_position) => grid_position.to_chunk(),
+        Err(_) => return,
+    };
+
+    for x in -CHUNK_LOAD_RADIUS..=CHUNK_LOAD_RADIUS {
+        for y in -CHUNK_LOAD_RADIUS..=CHUNK_LOAD_RADIUS {
+            let chunk = Chunk(player_chunk.0 + IVec2::new(x, y));
+            if loaded_chunks.0.contains_key(&chunk) {
+                continue;
+            }
+            let entity = spawn_chunk(&mut commands, &generator, chunk);
+            loaded_chunks.0.insert(chunk, entity);
+        }
+    }
+}
+
+fn spawn_chunk(commands: &mut Commands, generator: &WorldGenerator, chunk: Chunk) -> Entity {
+    commands
+        .spawn((
+            SpatialBundle::default(),
+            chunk,
+            Name::new(format!("Chunk {} {}", chunk.0.x, chunk.0.y)),
+        ))
+        .with_children(|child_builder| {
+            chunk.grid_positions().for_each(|grid_position| {
+                let tile_type = generator.tile_at(&grid_position);
+                child_builder.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: tile_type.color(),
+                            custom_size: Some(Vec2::splat(GridPosition::PIXELS_PER_TILE as f32)),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: grid_position.sprite_translation_z(TILE_Z),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    GridTile {
+                        display_priority: tile_type.display_priority(),
+                        tile_type,
+                    },
+                    grid_position,
+                ));
+            });
+        })
+        .id()
+}
+
+fn unload_distant_chunks(
+    mut commands: Commands,
+    mut loaded_chunks: ResMut<LoadedChunks>,
+    player: Query<&GridPosition, (With<Player>, Changed<GridPosition>)>,
+) {
+    let player_chunk = match player.get_single() {
+        Ok(grid_position) => grid_position.to_chunk(),
+        Err(_) => return,
+    };
+
+    loaded_chunks.0.retain(|chunk, entity| {
+        let keep = chunk_distance(chunk, &player_chunk) <= CHUNK_UNLOAD_RADIUS;
+        if !keep {
+            commands.entity(*entity).despawn_recursive();
+        }
+        keep
+    });
+}
@@ -1,7 +1,4 @@
-use bevy::prelude::*;
-
-use crate::input::camera::my_cursor_system;
-use crate::input::MyWorldCoords;
+use bevy::{prelude::*, utils::HashMap};
 
 pub struct GridPlugin;
 
@@ -9,20 +6,26 @@ impl Plugin for GridPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<GridTile>()
             .register_type::<TileType>()
+            .register_type::<Chunk>()
             .register_type::<GridPosition>()
-            .add_systems(
-                PreUpdate,
-                // Ordered to ensure that we're using this frame's mouse position
-                update_mouse_grid_location.after(my_cursor_system),
-            )
-            .insert_resource(HoveredGrid::new());
+            .insert_resource(HoveredGrid::new())
+            .insert_resource(OccupiedTiles::default());
     }
 }
 
+#[derive(Component, Reflect, Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
 pub struct Chunk(pub IVec2);
 
 impl Chunk {
     pub const CHUNK_SIZE: usize = 16;
+
+    /// Every grid position inside this chunk.
+    pub fn grid_positions(&self) -> impl Iterator<Item = GridPosition> {
+        let origin = self.0 * Self::CHUNK_SIZE as i32;
+        (0..Self::CHUNK_SIZE as i32).flat_map(move |x| {
+            (0..Self::CHUNK_SIZE as i32).map(move |y| GridPosition(origin + IVec2::new(x, y)))
+        })
+    }
 }
 
 #[derive(Component, Reflect, Debug, Clone, Eq, PartialEq, Hash, Default)]
@@ -42,19 +45,11 @@ impl GridPosition {
 
     pub fn to_chunk(&self) -> Chunk {
         Chunk(IVec2::new(
-            self.0.x / Chunk::CHUNK_SIZE as i32,
-            self.0.y / Chunk::CHUNK_SIZE as i32,
+            self.0.x.div_euclid(Chunk::CHUNK_SIZE as i32),
+            self.0.y.div_euclid(Chunk::CHUNK_SIZE as i32),
         ))
     }
 
-    pub fn sprite_translation(&self) -> Vec3 {
-        Vec3::new(
-            (self.0.x * Self::PIXELS_PER_TILE) as f32,
-            (self.0.y * Self::PIXELS_PER_TILE) as f32,
-            0.0,
-        )
-    }
-
     pub fn sprite_translation_z(&self, z: f32) -> Vec3 {
         Vec3::new(
             (self.0.x * Self::PIXELS_PER_TILE) as f32,
@@ -62,10 +57,6 @@ impl GridPosition {
             z,
         )
     }
-
-    pub fn value(&self) -> IVec2 {
-        self.0
-    }
 }
 
 #[derive(Resource)]
@@ -75,16 +66,61 @@ impl HoveredGrid {
     pub fn new() -> Self {
         HoveredGrid(GridPosition(IVec2::new(0, 0)))
     }
+}
+
+/// Index of the entity occupying each tile, consulted when placing and removing structures.
+#[derive(Resource, Default, Debug)]
+pub struct OccupiedTiles(pub HashMap<GridPosition, Entity>);
 
-    pub fn value(&self) -> IVec2 {
-        self.0.value()
+impl OccupiedTiles {
+    pub fn get(&self, tile: &GridPosition) -> Option<Entity> {
+        self.0.get(tile).copied()
+    }
+
+    pub fn is_free(&self, tiles: &[GridPosition]) -> bool {
+        tiles.iter().all(|tile| !self.0.contains_key(tile))
+    }
+
+    pub fn occupy(&mut self, tiles: Vec<GridPosition>, entity: Entity) {
+        tiles.into_iter().for_each(|tile| {
+            self.0.insert(tile, entity);
+        });
+    }
+
+    pub fn free(&mut self, entity: Entity) {
+        self.0.retain(|_, occupant| *occupant != entity);
     }
 }
 
-#[derive(Component, Reflect, Debug, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+#[derive(Component, Reflect, Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub enum TileType {
     Grass,
     Water,
+    Forest,
+    Stone,
+    Crystal,
+}
+
+impl TileType {
+    pub fn color(&self) -> Color {
+        use TileType::*;
+        match self {
+            Grass => Color::rgb(0.35, 0.6, 0.3),
+            Water => Color::rgb(0.2, 0.4, 0.8),
+            Forest => Color::rgb(0.15, 0.4, 0.15),
+            Stone => Color::rgb(0.5, 0.5, 0.5),
+            Crystal => Color::rgb(0.6, 0.4, 0.8),
+        }
+    }
+
+    pub fn display_priority(&self) -> i32 {
+        use TileType::*;
+        match self {
+            Water => 0,
+            Grass => 1,
+            Forest | Stone | Crystal => 2,
+        }
+    }
 }
 
 #[derive(Component, Reflect, Debug, Clone, PartialEq, PartialOrd, Hash, Eq, Ord)]
@@ -92,10 +128,3 @@ pub struct GridTile {
     pub display_priority: i32,
     pub tile_type: TileType,
 }
-
-fn update_mouse_grid_location(mycoords: Res<MyWorldCoords>, mut hovered_grid: ResMut<HoveredGrid>) {
-    let new_grid = GridPosition::from_position(mycoords.0);
-    if new_grid != hovered_grid.0 {
-        hovered_grid.0 = new_grid;
-    }
-}
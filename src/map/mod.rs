@@ -1,11 +1,17 @@
 use bevy::prelude::*;
 
+pub mod generation;
 pub mod grid;
+pub mod resource_nodes;
 
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(grid::GridPlugin);
+        app.add_plugins((
+            grid::GridPlugin,
+            generation::WorldGenerationPlugin,
+            resource_nodes::ResourceNodePlugin,
+        ));
     }
 }
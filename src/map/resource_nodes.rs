@@ -0,0 +1,93 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    common::GameState,
+    simulation::{TickTimer, NORMAL_SPEED},
+};
+
+use super::{
+    generation::WorldGenerator,
+    grid::{GridPosition, TileType},
+};
+
+// How much each resource tile holds before anything is gathered from it
+pub const NODE_AMOUNT_PER_TILE: u32 = 25;
+// Forests regrow one log per tile each interval, stone and crystal never come back
+const FOREST_REGROWTH_SECONDS: f32 = 30.0;
+
+pub(super) struct ResourceNodePlugin;
+
+impl Plugin for ResourceNodePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ResourceNodes::default())
+            .add_systems(
+                FixedUpdate,
+                regrow_forests.run_if(in_state(GameState::Playing)),
+            )
+            .register_type::<ResourceNodes>()
+            .register_type::<HashMap<GridPosition, u32>>();
+    }
+}
+
+/// What is left in every resource tile that has been gathered from. Untouched tiles are full,
+/// and gatherers sharing a tile draw down the same amount.
+#[derive(Resource, Reflect, Debug, Clone)]
+pub struct ResourceNodes {
+    pub remaining: HashMap<GridPosition, u32>,
+    pub regrowth: TickTimer,
+}
+
+impl Default for ResourceNodes {
+    fn default() -> Self {
+        ResourceNodes {
+            remaining: HashMap::default(),
+            regrowth: TickTimer::from_seconds(FOREST_REGROWTH_SECONDS),
+        }
+    }
+}
+
+impl ResourceNodes {
+    pub fn remaining(&self, tile: &GridPosition) -> u32 {
+        self.remaining
+            .get(tile)
+            .copied()
+            .unwrap_or(NODE_AMOUNT_PER_TILE)
+    }
+
+    pub fn total(&self, tiles: &[GridPosition]) -> u32 {
+        tiles.iter().map(|tile| self.remaining(tile)).sum()
+    }
+
+    /// Takes up to `amount` out of the tiles in order and returns how much was taken.
+    pub fn take(&mut self, tiles: &[GridPosition], amount: u32) -> u32 {
+        let mut taken = 0;
+        for tile in tiles {
+            if taken == amount {
+                break;
+            }
+            let remaining = self.remaining(tile);
+            let take = remaining.min(amount - taken);
+            if take > 0 {
+                self.remaining.insert(tile.clone(), remaining - take);
+                taken += take;
+            }
+        }
+        taken
+    }
+}
+
+fn regrow_forests(mut nodes: ResMut<ResourceNodes>, generator: Res<WorldGenerator>) {
+    let nodes = &mut *nodes;
+    if !nodes.regrowth.tick(NORMAL_SPEED) {
+        return;
+    }
+    for (tile, remaining) in nodes.remaining.iter_mut() {
+        if generator.tile_at(tile) == TileType::Forest {
+            *remaining += 1;
+        }
+    }
+    // Tiles that are full again don't need tracking
+    nodes
+        .remaining
+        .retain(|_, remaining| *remaining < NODE_AMOUNT_PER_TILE);
+}
@@ -0,0 +1,481 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    common::{text_input_unfocused, GameState},
+    crafting::{handle_crafting, CraftCompleteEvent, Crafter, CrafterState, CraftingCategories},
+    items::{
+        definitions::ItemRegistry,
+        inventory::{Inventory, ItemAmount},
+        ItemType,
+    },
+    recipes::{Recipe, RecipeId, Recipes},
+    research::AvailableRecipes,
+};
+
+use super::Player;
+
+pub(super) struct CraftingQueuePlugin;
+
+impl Plugin for CraftingQueuePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<QueueCraftEvent>()
+            .add_event::<CancelCraftEvent>()
+            .add_systems(
+                Update,
+                (
+                    queue_crafts,
+                    cancel_last_craft.run_if(text_input_unfocused),
+                    cancel_crafts,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            // Follows the handcraft on the same tick it finishes
+            .add_systems(
+                FixedUpdate,
+                (finish_queued_craft, start_queued_craft)
+                    .chain()
+                    .after(handle_crafting)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .register_type::<CraftingQueue>()
+            .register_type::<QueuedCraft>();
+    }
+}
+
+/// Handcrafts `count` of a recipe, crafting any missing ingredients first.
+#[derive(Event, Debug, Clone)]
+pub struct QueueCraftEvent {
+    pub recipe: RecipeId,
+    pub count: u32,
+}
+
+/// Cancels a queue entry by id, along with the crafts it was queued with.
+#[derive(Event, Debug, Clone)]
+pub struct CancelCraftEvent(pub u32);
+
+/// The player's handcrafting queue, crafted front to back.
+#[derive(Component, Reflect, Default, Clone, Debug)]
+pub struct CraftingQueue {
+    pub entries: Vec<QueuedCraft>,
+    pub next_id: u32,
+}
+
+/// Ingredients are taken from the player when queued and held here until crafted,
+/// so cancelling can give back exactly what was taken.
+#[derive(Reflect, Default, Clone, Debug)]
+pub struct QueuedCraft {
+    pub id: u32,
+    pub recipe: RecipeId,
+    pub remaining: u32,
+    pub ingredients: HashMap<ItemType, u32>,
+    // The entry this intermediate craft is making ingredients for
+    pub parent: Option<u32>,
+}
+
+impl QueuedCraft {
+    fn has_ingredients(&self, items: &[ItemAmount]) -> bool {
+        items.iter().all(|item_amount| {
+            self.ingredients
+                .get(&item_amount.item)
+                .copied()
+                .unwrap_or(0)
+                >= item_amount.amount.unwrap_or(0)
+        })
+    }
+
+    fn add_ingredients(&mut self, items: &[ItemAmount]) {
+        for item_amount in items {
+            *self.ingredients.entry(item_amount.item).or_insert(0) +=
+                item_amount.amount.unwrap_or(0);
+        }
+    }
+
+    fn remove_ingredients(&mut self, items: &[ItemAmount]) {
+        for item_amount in items {
+            if let Some(amount) = self.ingredients.get_mut(&item_amount.item) {
+                *amount = amount.saturating_sub(item_amount.amount.unwrap_or(0));
+            }
+        }
+        self.ingredients.retain(|_, amount| *amount > 0);
+    }
+
+    fn ingredient_amounts(&self) -> Vec<ItemAmount> {
+        self.ingredients
+            .iter()
+            .map(|(item, amount)| (*item, *amount).into())
+            .collect()
+    }
+}
+
+impl CraftingQueue {
+    /// Plans crafts for `count` of `recipe` out of `inventory`, returning the new entries
+    /// in the order they have to be crafted, or `None` if some ingredient can't be had
+    /// or there is nothing to craft.
+    pub fn plan(
+        &mut self,
+        recipe: &Recipe,
+        count: u32,
+        inventory: &mut Inventory,
+        categories: &CraftingCategories,
+        recipes: &Recipes,
+        available_recipes: &AvailableRecipes,
+    ) -> Option<Vec<QueuedCraft>> {
+        if count == 0 {
+            return None;
+        }
+        let handcraftable = recipes.available_for(categories, available_recipes);
+        let mut plan = vec![];
+        let mut next_id = self.next_id;
+        plan_craft(
+            recipe,
+            count,
+            None,
+            inventory,
+            &handcraftable,
+            &mut vec![],
+            &mut next_id,
+            &mut plan,
+        )?;
+        self.next_id = next_id;
+        Some(plan)
+    }
+
+    // The entry with the given id and every intermediate craft queued for it
+    fn with_descendants(&self, id: u32) -> Vec<u32> {
+        let mut ids = vec![id];
+        let mut index = 0;
+        while index < ids.len() {
+            let parent = ids[index];
+            ids.extend(
+                self.entries
+                    .iter()
+                    .filter(|entry| entry.parent == Some(parent))
+                    .map(|entry| entry.id),
+            );
+            index += 1;
+        }
+        ids
+    }
+
+    // Intermediate crafts only make sense together with what they were queued for
+    fn root_of(&self, id: u32) -> u32 {
+        let mut id = id;
+        while let Some(parent) = self
+            .entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .and_then(|entry| entry.parent)
+        {
+            id = parent;
+        }
+        id
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plan_craft(
+    recipe: &Recipe,
+    count: u32,
+    parent: Option<u32>,
+    inventory: &mut Inventory,
+    handcraftable: &[&Recipe],
+    visiting: &mut Vec<RecipeId>,
+    next_id: &mut u32,
+    plan: &mut Vec<QueuedCraft>,
+) -> Option<()> {
+    let mut entry = QueuedCraft {
+        id: *next_id,
+        recipe: recipe.id.clone(),
+        remaining: count,
+        ingredients: HashMap::default(),
+        parent,
+    };
+    *next_id += 1;
+    visiting.push(recipe.id.clone());
+
+    for input in recipe.input.iter() {
+        let needed = input.amount.unwrap_or(0).checked_mul(count)?;
+        let taken = needed.min(inventory.items.get(&input.item).copied().unwrap_or(0));
+        if taken > 0 {
+            let taken: Vec<ItemAmount> = vec![(input.item, taken).into()];
+            inventory.remove_items(&taken);
+            entry.add_ingredients(&taken);
+        }
+        let missing = needed - taken;
+        if missing == 0 {
+            continue;
+        }
+        // Craft whatever is missing, skipping recipes already being planned to avoid loops
+        let (intermediate, produced) = handcraftable.iter().find_map(|candidate| {
+            if visiting.contains(&candidate.id) {
+                return None;
+            }
+            candidate
+                .output
+                .iter()
+                .find(|output| output.item == input.item && output.amount.unwrap_or(0) > 0)
+                .map(|output| (*candidate, output.amount.unwrap_or(0)))
+        })?;
+        plan_craft(
+            intermediate,
+            missing.div_ceil(produced),
+            Some(entry.id),
+            inventory,
+            handcraftable,
+            visiting,
+            next_id,
+            plan,
+        )?;
+    }
+
+    visiting.pop();
+    plan.push(entry);
+    Some(())
+}
+
+fn queue_crafts(
+    mut events: EventReader<QueueCraftEvent>,
+    mut player: Query<(&mut Inventory, &mut CraftingQueue, &CraftingCategories), With<Player>>,
+    recipes: Res<Recipes>,
+    available_recipes: Res<AvailableRecipes>,
+) {
+    let (mut inventory, mut queue, categories) = player.single_mut();
+    for event in events.iter() {
+        let Some(recipe) = recipes.get(&event.recipe) else {
+            continue;
+        };
+        if event.count == 0 {
+            continue;
+        }
+        if !categories.supports(recipe) {
+            println!("{} can't be crafted by hand", recipe.id);
+            continue;
+        }
+        // Plan against a copy so nothing is taken unless every ingredient can be had
+        let mut planned_inventory = inventory.clone();
+        match queue.plan(
+            recipe,
+            event.count,
+            &mut planned_inventory,
+            categories,
+            &recipes,
+            &available_recipes,
+        ) {
+            Some(plan) => {
+                println!("Queued {} x{}", recipe.id, event.count);
+                *inventory = planned_inventory;
+                queue.entries.extend(plan);
+            }
+            None => println!("Missing ingredients for {} x{}", recipe.id, event.count),
+        }
+    }
+}
+
+// X cancels the most recently queued craft
+fn cancel_last_craft(
+    keys: Res<Input<KeyCode>>,
+    player: Query<&CraftingQueue, With<Player>>,
+    mut events: EventWriter<CancelCraftEvent>,
+) {
+    if !keys.just_pressed(KeyCode::X) {
+        return;
+    }
+    if let Some(entry) = player.single().entries.last() {
+        events.send(CancelCraftEvent(entry.id));
+    }
+}
+
+fn cancel_crafts(
+    mut events: EventReader<CancelCraftEvent>,
+    mut player: Query<(&mut Inventory, &mut CraftingQueue, &mut Crafter), With<Player>>,
+    registry: Res<ItemRegistry>,
+) {
+    let (mut inventory, mut queue, mut crafter) = player.single_mut();
+    for event in events.iter() {
+        let ids = queue.with_descendants(queue.root_of(event.0));
+        let mut refund: Vec<ItemAmount> = queue
+            .entries
+            .iter()
+            .filter(|entry| ids.contains(&entry.id))
+            .flat_map(|entry| entry.ingredient_amounts())
+            .collect();
+        // The craft in progress already took its inputs out of the front entry
+        let crafting_cancelled = queue
+            .entries
+            .first()
+            .is_some_and(|entry| ids.contains(&entry.id))
+            && crafter.state != CrafterState::Idle;
+        if crafting_cancelled {
+            if let Some(recipe) = crafter.recipe.as_ref() {
+                refund.extend(recipe.input.iter().copied());
+            }
+        }
+        if !inventory.has_space_for(&refund, &registry) {
+            println!("Not enough space to cancel crafting");
+            continue;
+        }
+        inventory.add_items(&refund, &registry);
+        queue.entries.retain(|entry| !ids.contains(&entry.id));
+        if crafting_cancelled {
+            crafter.recipe = None;
+            crafter.state = CrafterState::Idle;
+            crafter.progress = 0;
+        }
+    }
+}
+
+// Finished intermediates go straight to the craft they were made for
+fn finish_queued_craft(
+    mut events: EventReader<CraftCompleteEvent>,
+    mut player: Query<(Entity, &mut Inventory, &mut CraftingQueue), With<Player>>,
+    registry: Res<ItemRegistry>,
+) {
+    let (player_entity, mut inventory, mut queue) = player.single_mut();
+    for event in events.iter().filter(|event| event.entity == player_entity) {
+        let Some(entry) = queue.entries.first_mut() else {
+            continue;
+        };
+        entry.remaining = entry.remaining.saturating_sub(1);
+        let parent = entry.parent;
+        let finished = entry.remaining == 0;
+
+        if let Some(parent) = parent {
+            let output = inventory.remove_if_possible(&event.recipe.output);
+            if let Some(parent) = queue.entries.iter_mut().find(|entry| entry.id == parent) {
+                parent.add_ingredients(&output);
+            }
+        }
+        if finished {
+            // Anything left over, like extra intermediates, goes back to the player
+            let entry = queue.entries.remove(0);
+            inventory.add_items(&entry.ingredient_amounts(), &registry);
+        }
+    }
+}
+
+fn start_queued_craft(
+    mut player: Query<(&mut CraftingQueue, &mut Crafter), With<Player>>,
+    recipes: Res<Recipes>,
+) {
+    let (mut queue, mut crafter) = player.single_mut();
+    if crafter.state != CrafterState::Idle {
+        return;
+    }
+    let Some(entry) = queue.entries.first_mut() else {
+        return;
+    };
+    let Some(recipe) = recipes.get(&entry.recipe) else {
+        return;
+    };
+    if !entry.has_ingredients(&recipe.input) {
+        return;
+    }
+    entry.remove_ingredients(&recipe.input);
+    crafter.recipe = Some(recipe.clone());
+    crafter.progress = 0;
+    // The queue already holds the inputs, so go straight to assembling
+    crafter.state = CrafterState::Assembling(false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipes::CraftingCategory;
+
+    fn recipe(id: &str, input: Vec<(ItemType, u32)>, output: Vec<(ItemType, u32)>) -> Recipe {
+        Recipe {
+            id: id.into(),
+            input: input.into_iter().map(Into::into).collect(),
+            output: output.into_iter().map(Into::into).collect(),
+            cost: 1.0,
+            categories: vec![CraftingCategory::Handcraft],
+        }
+    }
+
+    // Planks from wood, and toys from planks and crystal
+    fn recipes() -> (Recipes, AvailableRecipes) {
+        let recipes = [
+            recipe(
+                "test::plank",
+                vec![(ItemType::WOOD, 2)],
+                vec![(ItemType::PLANK, 1)],
+            ),
+            recipe(
+                "test::toy",
+                vec![(ItemType::PLANK, 2), (ItemType::CRYSTAL, 1)],
+                vec![(ItemType::TOY, 1)],
+            ),
+        ];
+        let available = AvailableRecipes(recipes.iter().map(|recipe| recipe.id.clone()).collect());
+        let recipes = Recipes(
+            recipes
+                .into_iter()
+                .map(|recipe| (recipe.id.clone(), recipe))
+                .collect(),
+        );
+        (recipes, available)
+    }
+
+    fn plan(
+        queue: &mut CraftingQueue,
+        id: &str,
+        count: u32,
+        inventory: &mut Inventory,
+    ) -> Option<Vec<QueuedCraft>> {
+        let (recipes, available) = recipes();
+        let recipe = recipes.get(&id.into()).unwrap().clone();
+        let categories = CraftingCategories(vec![CraftingCategory::Handcraft]);
+        queue.plan(&recipe, count, inventory, &categories, &recipes, &available)
+    }
+
+    #[test]
+    fn crafts_missing_ingredients_first() {
+        let mut queue = CraftingQueue::default();
+        let mut inventory = Inventory::new(
+            10,
+            vec![
+                (ItemType::WOOD, 5).into(),
+                (ItemType::PLANK, 1).into(),
+                (ItemType::CRYSTAL, 1).into(),
+            ],
+        );
+
+        let plan = plan(&mut queue, "test::toy", 1, &mut inventory).unwrap();
+
+        assert_eq!(plan.len(), 2);
+        let (plank, toy) = (&plan[0], &plan[1]);
+        assert_eq!(plank.recipe, "test::plank".into());
+        assert_eq!(plank.remaining, 1);
+        assert_eq!(plank.parent, Some(toy.id));
+        assert_eq!(plank.ingredients.get(&ItemType::WOOD), Some(&2));
+        assert_eq!(toy.recipe, "test::toy".into());
+        assert_eq!(toy.ingredients.get(&ItemType::PLANK), Some(&1));
+        assert_eq!(toy.ingredients.get(&ItemType::CRYSTAL), Some(&1));
+        // Only what the plan needs is taken
+        assert_eq!(inventory.items.get(&ItemType::WOOD), Some(&3));
+        assert_eq!(queue.next_id, 2);
+    }
+
+    #[test]
+    fn missing_raw_ingredients_plan_nothing() {
+        let mut queue = CraftingQueue::default();
+        // Enough crystal, but only wood for one of the two planks
+        let mut inventory = Inventory::new(
+            10,
+            vec![(ItemType::WOOD, 3).into(), (ItemType::CRYSTAL, 1).into()],
+        );
+
+        assert!(plan(&mut queue, "test::toy", 1, &mut inventory).is_none());
+        assert_eq!(queue.next_id, 0);
+    }
+
+    #[test]
+    fn nothing_to_craft_plans_nothing() {
+        let mut queue = CraftingQueue::default();
+        let mut inventory = Inventory::new(10, vec![(ItemType::WOOD, 2).into()]);
+
+        assert!(plan(&mut queue, "test::plank", 0, &mut inventory).is_none());
+        assert!(plan(&mut queue, "test::plank", u32::MAX, &mut inventory).is_none());
+    }
+}
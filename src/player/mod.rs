@@ -1,24 +1,32 @@
 use crate::{
-    common::Held,
-    crafting::{Crafter, CrafterState},
+    common::{GameState, Held},
+    crafting::{Crafter, CraftingCategories},
     items::{inventory::Inventory, ItemType},
     map::grid::GridPosition,
-    recipes::{Recipe, RecipeType},
+    recipes::CraftingCategory,
     Speed,
 };
 
 use bevy::prelude::*;
 
-use self::events::PlayerMoveEvent;
+use self::{
+    crafting_queue::{CraftingQueue, CraftingQueuePlugin},
+    events::PlayerMoveEvent,
+};
 
+pub mod crafting_queue;
 pub mod events;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_player)
-            .add_systems(Update, (player_movement_controls, player_craft))
+        app.add_plugins(CraftingQueuePlugin)
+            .add_systems(Startup, spawn_player)
+            .add_systems(
+                Update,
+                player_movement_controls.run_if(in_state(GameState::Playing)),
+            )
             .add_event::<PlayerMoveEvent>();
     }
 }
@@ -37,26 +45,29 @@ pub struct FaePlayerBundle {
     pub speed: Speed,
     pub inventory: Inventory,
     pub crafter: Crafter,
+    pub crafting_categories: CraftingCategories,
+    pub crafting_queue: CraftingQueue,
     pub held: Held,
 }
 
 impl Default for FaePlayerBundle {
     fn default() -> Self {
-        use ItemType::*;
         FaePlayerBundle {
             player: Player,
             player_move: PlayerMove(None),
             speed: Speed(200.0),
             inventory: Inventory::new(
-                10,
+                20,
                 vec![
-                    (Wood, 10).into(),
-                    (Crystal, 10).into(),
-                    (Stone, 10).into(),
-                    (Toy, 10).into(),
+                    (ItemType::WOOD, 10).into(),
+                    (ItemType::CRYSTAL, 20).into(),
+                    (ItemType::STONE, 20).into(),
+                    (ItemType::TOY, 10).into(),
                 ],
             ),
             crafter: Crafter::new(),
+            crafting_categories: CraftingCategories(vec![CraftingCategory::Handcraft]),
+            crafting_queue: CraftingQueue::default(),
             held: Held(None),
             grid_position: GridPosition::default(),
         }
@@ -92,17 +103,3 @@ fn player_movement_controls(
         }
     }
 }
-
-fn player_craft(
-    mut commands: Commands,
-    input: Res<Input<KeyCode>>,
-    mut player: Query<&mut Crafter, With<Player>>,
-) {
-    for mut assembler in &mut player {
-        if input.just_pressed(KeyCode::Space) && assembler.state == CrafterState::Idle {
-            println!("Crafting!");
-            assembler.recipe = Some(Recipe::from(RecipeType::WoodToToy));
-            assembler.state = CrafterState::Pending(false); // Don't repeat crafting for the player
-        }
-    }
-}
@@ -0,0 +1,454 @@
+use bevy::utils::HashMap;
+use strum::IntoEnumIterator;
+
+use crate::{
+    items::ItemType,
+    recipes::{CraftingCategory, Recipe, RecipeId},
+    simulation::{ticks_to_seconds, NORMAL_SPEED},
+    structures::{smelter::Burner, StructureType},
+};
+
+/// What makes an item in a plan. Crafts with no `structure` are handcrafted by the player.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Producer {
+    Crafter {
+        structure: Option<StructureType>,
+        recipe: RecipeId,
+    },
+    Gatherer(StructureType),
+}
+
+/// One kind of producer in a plan, with how many of them it takes running all the time.
+#[derive(Clone, Debug)]
+pub struct PlanStep {
+    pub producer: Producer,
+    pub count: f32,
+    // Everything the producers make per minute, byproducts included
+    pub outputs: Vec<(ItemType, f32)>,
+}
+
+/// The producers and raw inputs needed to make an item at a target rate.
+#[derive(Clone, Debug, Default)]
+pub struct RatePlan {
+    pub steps: Vec<PlanStep>,
+    // Items nothing in the plan can make, per minute
+    pub raw_inputs: Vec<(ItemType, f32)>,
+}
+
+impl RatePlan {
+    fn add_step(&mut self, producer: Producer, count: f32, outputs: Vec<(ItemType, f32)>) {
+        let Some(step) = self.steps.iter_mut().find(|step| step.producer == producer) else {
+            self.steps.push(PlanStep {
+                producer,
+                count,
+                outputs,
+            });
+            return;
+        };
+        step.count += count;
+        for (item, rate) in outputs {
+            match step
+                .outputs
+                .iter_mut()
+                .find(|(existing, _)| *existing == item)
+            {
+                Some((_, total)) => *total += rate,
+                None => step.outputs.push((item, rate)),
+            }
+        }
+    }
+
+    fn add_raw_input(&mut self, item: ItemType, rate: f32) {
+        match self
+            .raw_inputs
+            .iter_mut()
+            .find(|(existing, _)| *existing == item)
+        {
+            Some((_, total)) => *total += rate,
+            None => self.raw_inputs.push((item, rate)),
+        }
+    }
+}
+
+/// Works out structure counts for a target output rate by walking the recipe graph.
+///
+/// Each item is made by one recipe, preferring ones a structure can craft and that make
+/// the most of it. Byproducts are listed but not credited against other demand.
+pub struct RateCalculator<'a> {
+    recipes: Vec<&'a Recipe>,
+    structures: Vec<StructureType>,
+    // Speed percentages for structures that don't run at normal speed
+    speeds: HashMap<StructureType, u32>,
+}
+
+impl<'a> RateCalculator<'a> {
+    pub fn new(
+        recipes: impl IntoIterator<Item = &'a Recipe>,
+        structures: impl IntoIterator<Item = StructureType>,
+    ) -> Self {
+        let mut recipes: Vec<&Recipe> = recipes.into_iter().collect();
+        recipes.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut structures: Vec<StructureType> = structures.into_iter().collect();
+        // Plans should come out the same whatever order the structures were unlocked in
+        structures
+            .sort_by_key(|structure| StructureType::iter().position(|other| other == *structure));
+        RateCalculator {
+            recipes,
+            structures,
+            speeds: HashMap::default(),
+        }
+    }
+
+    pub fn with_speed(mut self, structure: StructureType, speed: u32) -> Self {
+        self.speeds.insert(structure, speed);
+        self
+    }
+
+    /// Plans for `per_minute` of `item`.
+    pub fn plan(&self, item: ItemType, per_minute: f32) -> RatePlan {
+        let mut plan = RatePlan::default();
+        self.add_demand(item, per_minute, &mut plan, &mut Vec::new());
+        plan
+    }
+
+    fn speed(&self, structure: Option<StructureType>) -> f32 {
+        let speed = structure
+            .and_then(|structure| self.speeds.get(&structure).copied())
+            .unwrap_or(NORMAL_SPEED);
+        speed as f32 / NORMAL_SPEED as f32
+    }
+
+    fn add_demand(
+        &self,
+        item: ItemType,
+        per_minute: f32,
+        plan: &mut RatePlan,
+        // Items further up the chain, so recipe loops end as raw inputs
+        making: &mut Vec<ItemType>,
+    ) {
+        if per_minute <= 0.0 {
+            return;
+        }
+        if let Some((gatherer, rate)) = self.gatherer_for(item) {
+            plan.add_step(
+                Producer::Gatherer(gatherer),
+                per_minute / rate,
+                vec![(item, per_minute)],
+            );
+            return;
+        }
+        if making.contains(&item) {
+            plan.add_raw_input(item, per_minute);
+            return;
+        }
+        let Some((recipe, structure)) = self.recipe_for(item) else {
+            plan.add_raw_input(item, per_minute);
+            return;
+        };
+
+        let made_per_craft = amount_of(item, recipe);
+        let crafts_per_minute = per_minute / made_per_craft as f32;
+        let seconds_per_craft = ticks_to_seconds(recipe.cost_ticks());
+        let crafts_per_crafter = 60.0 / seconds_per_craft * self.speed(structure);
+        let count = crafts_per_minute / crafts_per_crafter;
+        let outputs = recipe
+            .output
+            .iter()
+            .map(|output| {
+                let amount = output.amount.unwrap_or(0) as f32;
+                (output.item, crafts_per_minute * amount)
+            })
+            .collect();
+        plan.add_step(
+            Producer::Crafter {
+                structure,
+                recipe: recipe.id.clone(),
+            },
+            count,
+            outputs,
+        );
+
+        making.push(item);
+        for input in recipe.input.iter() {
+            let amount = input.amount.unwrap_or(0) as f32;
+            self.add_demand(input.item, crafts_per_minute * amount, plan, making);
+        }
+        if structure == Some(StructureType::Smelter) {
+            // Burning smelters get through fuel for as long as they are working
+            let burner = Burner::default();
+            let fuel_per_minute = count * 60.0 / ticks_to_seconds(burner.burn_ticks);
+            self.add_demand(burner.fuel, fuel_per_minute, plan, making);
+        }
+        making.pop();
+    }
+
+    // The fastest available gatherer of the item, with what one makes per minute
+    fn gatherer_for(&self, item: ItemType) -> Option<(StructureType, f32)> {
+        self.structures
+            .iter()
+            .filter_map(|structure| {
+                let spawner = structure.get_gathering_spawner()?;
+                let amount = spawner
+                    .output
+                    .iter()
+                    .find(|output| output.item == item)?
+                    .amount
+                    .unwrap_or(0) as f32;
+                let seconds = ticks_to_seconds(spawner.timer.duration);
+                Some((
+                    *structure,
+                    amount * 60.0 / seconds * self.speed(Some(*structure)),
+                ))
+            })
+            .filter(|(_, rate)| *rate > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    // Structure crafted recipes beat handcrafted ones, then whichever makes the most per craft
+    fn recipe_for(&self, item: ItemType) -> Option<(&'a Recipe, Option<StructureType>)> {
+        let mut best: Option<(&'a Recipe, Option<StructureType>)> = None;
+        for recipe in self.recipes.iter().copied() {
+            let made = amount_of(item, recipe);
+            // Recipes that use up the item they make would never get ahead
+            if made == 0 || recipe.input.iter().any(|input| input.item == item) {
+                continue;
+            }
+            let structure = self.structures.iter().copied().find(|structure| {
+                structure
+                    .crafting_categories()
+                    .iter()
+                    .any(|category| recipe.categories.contains(category))
+            });
+            if structure.is_none() && !recipe.categories.contains(&CraftingCategory::Handcraft) {
+                continue;
+            }
+            let better = match best {
+                None => true,
+                Some((current, current_structure)) => {
+                    (structure.is_some(), made)
+                        > (current_structure.is_some(), amount_of(item, current))
+                }
+            };
+            if better {
+                best = Some((recipe, structure));
+            }
+        }
+        best
+    }
+}
+
+fn amount_of(item: ItemType, recipe: &Recipe) -> u32 {
+    recipe
+        .output
+        .iter()
+        .filter(|output| output.item == item)
+        .map(|output| output.amount.unwrap_or(0))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(
+        id: &str,
+        input: Vec<(ItemType, u32)>,
+        output: Vec<(ItemType, u32)>,
+        cost: f32,
+        category: CraftingCategory,
+    ) -> Recipe {
+        Recipe {
+            id: id.into(),
+            input: input.into_iter().map(Into::into).collect(),
+            output: output.into_iter().map(Into::into).collect(),
+            cost,
+            categories: vec![category],
+        }
+    }
+
+    fn plank_recipe() -> Recipe {
+        recipe(
+            "test::plank",
+            vec![(ItemType::WOOD, 2)],
+            vec![(ItemType::PLANK, 1)],
+            2.0,
+            CraftingCategory::Assembler,
+        )
+    }
+
+    fn count_of(plan: &RatePlan, producer: Producer) -> f32 {
+        plan.steps
+            .iter()
+            .find(|step| step.producer == producer)
+            .map_or(0.0, |step| step.count)
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn plans_a_single_recipe() {
+        let recipes = [plank_recipe()];
+        let calculator = RateCalculator::new(
+            recipes.iter(),
+            [StructureType::Assembler, StructureType::WoodFairy],
+        );
+        let plan = calculator.plan(ItemType::PLANK, 30.0);
+
+        let plank = Producer::Crafter {
+            structure: Some(StructureType::Assembler),
+            recipe: "test::plank".into(),
+        };
+        assert_close(count_of(&plan, plank), 1.0);
+        // 60 wood a minute at 6 per fairy
+        assert_close(
+            count_of(&plan, Producer::Gatherer(StructureType::WoodFairy)),
+            10.0,
+        );
+        assert!(plan.raw_inputs.is_empty());
+    }
+
+    #[test]
+    fn plans_a_chain_of_recipes() {
+        let recipes = [
+            plank_recipe(),
+            recipe(
+                "test::toy",
+                vec![(ItemType::PLANK, 2), (ItemType::CRYSTAL, 1)],
+                vec![(ItemType::TOY, 1)],
+                5.0,
+                CraftingCategory::Assembler,
+            ),
+        ];
+        let calculator = RateCalculator::new(
+            recipes.iter(),
+            [
+                StructureType::Assembler,
+                StructureType::WoodFairy,
+                StructureType::CrystalFairy,
+            ],
+        );
+        let plan = calculator.plan(ItemType::TOY, 6.0);
+
+        let toy = Producer::Crafter {
+            structure: Some(StructureType::Assembler),
+            recipe: "test::toy".into(),
+        };
+        let plank = Producer::Crafter {
+            structure: Some(StructureType::Assembler),
+            recipe: "test::plank".into(),
+        };
+        assert_close(count_of(&plan, toy), 0.5);
+        assert_close(count_of(&plan, plank), 0.4);
+        assert_close(
+            count_of(&plan, Producer::Gatherer(StructureType::WoodFairy)),
+            4.0,
+        );
+        assert_close(
+            count_of(&plan, Producer::Gatherer(StructureType::CrystalFairy)),
+            1.5,
+        );
+    }
+
+    #[test]
+    fn smelters_need_fuel() {
+        let recipes = [recipe(
+            "test::brick",
+            vec![(ItemType::STONE, 2)],
+            vec![(ItemType::STONE_BRICK, 1)],
+            3.0,
+            CraftingCategory::Smelter,
+        )];
+        let calculator = RateCalculator::new(
+            recipes.iter(),
+            [
+                StructureType::Smelter,
+                StructureType::StoneFairy,
+                StructureType::WoodFairy,
+            ],
+        );
+        let plan = calculator.plan(ItemType::STONE_BRICK, 20.0);
+
+        let brick = Producer::Crafter {
+            structure: Some(StructureType::Smelter),
+            recipe: "test::brick".into(),
+        };
+        assert_close(count_of(&plan, brick), 1.0);
+        assert_close(
+            count_of(&plan, Producer::Gatherer(StructureType::StoneFairy)),
+            40.0 / 6.0,
+        );
+        // One smelter burns a log every 6 seconds
+        assert_close(
+            count_of(&plan, Producer::Gatherer(StructureType::WoodFairy)),
+            10.0 / 6.0,
+        );
+    }
+
+    #[test]
+    fn lists_byproducts() {
+        let recipes = [recipe(
+            "test::polish",
+            vec![(ItemType::CRYSTAL, 1)],
+            vec![(ItemType::POLISHED_CRYSTAL, 1), (ItemType::FAE_DUST, 2)],
+            1.0,
+            CraftingCategory::Assembler,
+        )];
+        let calculator = RateCalculator::new(
+            recipes.iter(),
+            [StructureType::Assembler, StructureType::CrystalFairy],
+        );
+        let plan = calculator.plan(ItemType::POLISHED_CRYSTAL, 10.0);
+
+        let polish = plan
+            .steps
+            .iter()
+            .find(|step| {
+                step.producer
+                    == Producer::Crafter {
+                        structure: Some(StructureType::Assembler),
+                        recipe: "test::polish".into(),
+                    }
+            })
+            .unwrap();
+        let dust = polish
+            .outputs
+            .iter()
+            .find(|(item, _)| *item == ItemType::FAE_DUST)
+            .map(|(_, rate)| *rate);
+        assert_close(dust.unwrap(), 20.0);
+    }
+
+    #[test]
+    fn recipe_cycles_end_in_raw_inputs() {
+        let recipes = [
+            recipe(
+                "test::split",
+                vec![(ItemType::PLANK, 1)],
+                vec![(ItemType::TOY, 2)],
+                1.0,
+                CraftingCategory::Assembler,
+            ),
+            recipe(
+                "test::join",
+                vec![(ItemType::TOY, 1)],
+                vec![(ItemType::PLANK, 1)],
+                1.0,
+                CraftingCategory::Assembler,
+            ),
+        ];
+        let calculator = RateCalculator::new(recipes.iter(), [StructureType::Assembler]);
+        let plan = calculator.plan(ItemType::TOY, 10.0);
+
+        assert_eq!(plan.steps.len(), 2);
+        assert_eq!(plan.raw_inputs.len(), 1);
+        assert_eq!(plan.raw_inputs[0].0, ItemType::TOY);
+        assert_close(plan.raw_inputs[0].1, 5.0);
+    }
+}
@@ -1,80 +1,165 @@
 use crate::{
+    crafting::{Crafter, CraftingCategories},
+    data::{loaded_definitions, DataFolderPlugin, DataSet},
     items::{inventory::ItemAmount, ItemType},
     research::AvailableRecipes,
+    simulation::seconds_to_ticks,
+};
+use bevy::{
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::HashMap,
 };
-use bevy::prelude::*;
 use core::fmt;
+use serde::Deserialize;
+
+pub struct RecipePlugin;
 
-use strum_macros::EnumIter;
+impl Plugin for RecipePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(DataFolderPlugin::<RecipeSet>::default())
+            .insert_resource(Recipes::default())
+            .add_systems(
+                Update,
+                (
+                    update_recipes.run_if(on_event::<AssetEvent<RecipeSet>>()),
+                    refresh_crafter_recipes,
+                )
+                    .chain(),
+            )
+            .register_type::<Recipe>()
+            .register_type::<RecipeId>()
+            .register_type::<CraftingCategory>();
+    }
+}
 
 #[derive(Reflect, Clone, Debug)]
 pub struct Recipe {
-    pub recipe_type: RecipeType,
+    pub id: RecipeId,
     pub input: Vec<ItemAmount>,  // Input cost
     pub output: Vec<ItemAmount>, // Production
     pub cost: f32,               // Time to craft
+    pub categories: Vec<CraftingCategory>,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect, EnumIter, Resource)]
-pub enum RecipeType {
-    WoodToToy,
-    CrystalToToy,
+impl Recipe {
+    /// The crafting time in simulation ticks at normal speed.
+    pub fn cost_ticks(&self) -> u32 {
+        seconds_to_ticks(self.cost)
+    }
 }
 
-impl Iterator for RecipeType {
-    type Item = RecipeType;
+/// Identifies a recipe across asset files, e.g. `core::wood-to-toy`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect, Deserialize)]
+#[serde(transparent)]
+pub struct RecipeId(pub String);
 
-    fn next(&mut self) -> Option<Self::Item> {
-        use RecipeType::*;
-        match self {
-            WoodToToy => Some(CrystalToToy),
-            CrystalToToy => Some(WoodToToy),
-        }
+impl From<&str> for RecipeId {
+    fn from(id: &str) -> Self {
+        RecipeId(id.to_string())
     }
 }
 
-impl RecipeType {
-    pub fn next_available_recipe(self, available_recipes: &AvailableRecipes) -> RecipeType {
-        match self
-            .into_iter()
-            .filter(|r| available_recipes.0.contains(r))
-            .next()
-        {
-            Some(recipe) => recipe,
-            None => {
-                println!("");
-                RecipeType::WoodToToy
-            }
-        }
+impl fmt::Display for RecipeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-impl From<RecipeType> for Recipe {
-    fn from(recipe_type: RecipeType) -> Self {
-        use ItemType::*;
-        use RecipeType::*;
-        match recipe_type {
-            WoodToToy => Recipe {
-                recipe_type,
-                input: vec![(Wood, 3).into()],
-                output: vec![(Toy, 1).into()],
-                cost: 5.0,
-            },
-            CrystalToToy => Recipe {
-                recipe_type,
-                input: vec![(Crystal, 1).into()],
-                output: vec![(Toy, 1).into()],
-                cost: 10.0,
-            },
+/// The kinds of crafter a recipe can be made in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect, Deserialize)]
+pub enum CraftingCategory {
+    Handcraft,
+    Assembler,
+    Smelter,
+    Enchanter,
+}
+
+/// All recipes currently loaded from the recipe asset files, keyed by id.
+#[derive(Resource, Default, Debug)]
+pub struct Recipes(pub HashMap<RecipeId, Recipe>);
+
+impl Recipes {
+    pub fn get(&self, id: &RecipeId) -> Option<&Recipe> {
+        self.0.get(id)
+    }
+
+    /// Available recipes a crafter with the given categories can make, sorted by id.
+    pub fn available_for(
+        &self,
+        categories: &CraftingCategories,
+        available_recipes: &AvailableRecipes,
+    ) -> Vec<&Recipe> {
+        let mut recipes: Vec<&Recipe> = self
+            .0
+            .values()
+            .filter(|recipe| available_recipes.0.contains(&recipe.id))
+            .filter(|recipe| categories.supports(recipe))
+            .collect();
+        recipes.sort_by(|a, b| a.id.cmp(&b.id));
+        recipes
+    }
+}
+
+/// A single asset file of recipe definitions.
+#[derive(Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "5c4f2a8e-3d1b-4f0e-9b6a-7e2d8c1f4a93"]
+pub struct RecipeSet {
+    pub recipes: Vec<RecipeDefinition>,
+}
+
+impl DataSet for RecipeSet {
+    type Definition = RecipeDefinition;
+
+    const FOLDER: &'static str = "recipes";
+    const EXTENSIONS: &'static [&'static str] = &["recipes.ron"];
+
+    fn definitions(&self) -> &[RecipeDefinition] {
+        &self.recipes
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RecipeDefinition {
+    pub id: RecipeId,
+    pub input: Vec<(ItemType, u32)>,
+    pub output: Vec<(ItemType, u32)>,
+    pub cost: f32,
+    pub categories: Vec<CraftingCategory>,
+}
+
+impl From<RecipeDefinition> for Recipe {
+    fn from(definition: RecipeDefinition) -> Self {
+        Recipe {
+            id: definition.id,
+            input: definition.input.into_iter().map(Into::into).collect(),
+            output: definition.output.into_iter().map(Into::into).collect(),
+            cost: definition.cost,
+            categories: definition.categories,
         }
     }
 }
 
-impl fmt::Display for RecipeType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            RecipeType::WoodToToy => write!(f, "core::wood-to-toy"),
-            RecipeType::CrystalToToy => write!(f, "core::crystal-to-toy"),
+fn update_recipes(recipe_sets: Res<Assets<RecipeSet>>, mut recipes: ResMut<Recipes>) {
+    recipes.0 = loaded_definitions(&recipe_sets)
+        .map(|definition| (definition.id.clone(), Recipe::from(definition)))
+        .collect();
+    println!("Loaded {} recipes", recipes.0.len());
+}
+
+fn refresh_crafter_recipes(recipes: Res<Recipes>, mut crafters: Query<&mut Crafter>) {
+    if !recipes.is_changed() {
+        return;
+    }
+    // Hot reloaded recipes take effect on crafters that are already using them
+    for mut crafter in &mut crafters {
+        let updated = crafter
+            .recipe
+            .as_ref()
+            .and_then(|recipe| recipes.get(&recipe.id))
+            .cloned();
+        if updated.is_some() {
+            crafter.recipe = updated;
         }
     }
 }
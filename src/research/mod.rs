@@ -1,17 +1,171 @@
 use bevy::{prelude::*, utils::HashSet};
-
-use crate::recipes::RecipeType;
 use strum::IntoEnumIterator;
 
+use crate::{
+    common::{text_input_unfocused, GameState},
+    recipes::{RecipeId, Recipes},
+    structures::StructureType,
+};
+
+use self::technology::{Technologies, Technology, TechnologyId, TechnologyPlugin};
+
+pub mod technology;
+
 pub struct ResearchPlugin;
 
-#[derive(Resource, Reflect)]
-pub struct AvailableRecipes(pub HashSet<RecipeType>);
+#[derive(Resource, Reflect, Default)]
+pub struct AvailableRecipes(pub HashSet<RecipeId>);
+
+/// Structures the player is allowed to build.
+#[derive(Resource, Reflect, Default)]
+pub struct AvailableStructures(pub HashSet<StructureType>);
+
+/// Completed technologies and the one currently being researched.
+#[derive(Resource, Reflect, Default, Debug)]
+pub struct ResearchState {
+    pub completed: HashSet<TechnologyId>,
+    pub current: Option<ResearchProgress>,
+}
+
+/// How many units of the current technology the libraries have studied so far.
+#[derive(Reflect, Default, Debug, Clone)]
+pub struct ResearchProgress {
+    pub technology: TechnologyId,
+    pub units_completed: u32,
+}
+
+impl ResearchProgress {
+    pub fn new(technology: TechnologyId) -> Self {
+        ResearchProgress {
+            technology,
+            units_completed: 0,
+        }
+    }
+}
 
 impl Plugin for ResearchPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(AvailableRecipes(
-            RecipeType::iter().collect::<HashSet<RecipeType>>(),
-        ));
+        app.add_plugins(TechnologyPlugin)
+            .insert_resource(AvailableRecipes::default())
+            .insert_resource(AvailableStructures::default())
+            .insert_resource(ResearchState::default())
+            .add_systems(
+                Update,
+                (
+                    select_research.run_if(text_input_unfocused),
+                    complete_research,
+                    update_unlocks,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .register_type::<ResearchState>()
+            .register_type::<ResearchProgress>();
+    }
+}
+
+impl ResearchState {
+    /// Whether every prerequisite is done and the technology itself isn't.
+    pub fn can_research(&self, technologies: &Technologies, id: &TechnologyId) -> bool {
+        technologies.get(id).is_some_and(|technology| {
+            !self.completed.contains(id)
+                && technology
+                    .prerequisites
+                    .iter()
+                    .all(|prerequisite| self.completed.contains(prerequisite))
+        })
     }
+
+    /// The technology currently being researched, if it is still loaded.
+    pub fn current_technology<'a>(&self, technologies: &'a Technologies) -> Option<&'a Technology> {
+        self.current
+            .as_ref()
+            .and_then(|progress| technologies.get(&progress.technology))
+    }
+
+    /// The researchable technology after the current one in id order, wrapping around.
+    pub fn next_researchable(&self, technologies: &Technologies) -> Option<TechnologyId> {
+        let mut ids: Vec<&TechnologyId> = technologies
+            .0
+            .keys()
+            .filter(|id| self.can_research(technologies, id))
+            .collect();
+        ids.sort();
+        let current = self.current.as_ref().map(|progress| &progress.technology);
+        ids.iter()
+            .find(|id| Some(**id) > current)
+            .or(ids.first())
+            .map(|id| (*id).clone())
+    }
+}
+
+// Pick what to research with T, switching technologies drops the current progress
+fn select_research(
+    keys: Res<Input<KeyCode>>,
+    technologies: Res<Technologies>,
+    mut research: ResMut<ResearchState>,
+) {
+    if !keys.just_pressed(KeyCode::T) {
+        return;
+    }
+    match research.next_researchable(&technologies) {
+        Some(id) => {
+            println!("Researching {}", id);
+            research.current = Some(ResearchProgress::new(id));
+        }
+        None => println!("Nothing left to research"),
+    }
+}
+
+fn complete_research(technologies: Res<Technologies>, mut research: ResMut<ResearchState>) {
+    let Some(progress) = research.current.as_ref() else {
+        return;
+    };
+    let Some(technology) = technologies.get(&progress.technology) else {
+        return;
+    };
+    if progress.units_completed < technology.units {
+        return;
+    }
+    println!("Research complete: {}", technology.name);
+    research.completed.insert(technology.id.clone());
+    research.current = None;
+}
+
+// Anything no technology unlocks is available from the start
+fn update_unlocks(
+    recipes: Res<Recipes>,
+    technologies: Res<Technologies>,
+    research: Res<ResearchState>,
+    mut available_recipes: ResMut<AvailableRecipes>,
+    mut available_structures: ResMut<AvailableStructures>,
+) {
+    if !recipes.is_changed() && !technologies.is_changed() && !research.is_changed() {
+        return;
+    }
+    let unlocked: Vec<_> = research
+        .completed
+        .iter()
+        .filter_map(|id| technologies.get(id))
+        .collect();
+
+    available_recipes.0 = recipes
+        .0
+        .keys()
+        .filter(|id| {
+            !technologies.locks_recipe(id)
+                || unlocked
+                    .iter()
+                    .any(|technology| technology.recipes.contains(id))
+        })
+        .cloned()
+        .collect();
+    available_structures.0 = StructureType::iter()
+        .filter(|structure| {
+            !technologies.locks_structure(structure)
+                || unlocked
+                    .iter()
+                    .any(|technology| technology.structures.contains(structure))
+        })
+        .collect();
 }
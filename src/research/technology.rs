@@ -0,0 +1,139 @@
+use bevy::{
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    utils::HashMap,
+};
+use core::fmt;
+use serde::Deserialize;
+
+use crate::{
+    data::{loaded_definitions, DataFolderPlugin, DataSet},
+    items::{inventory::ItemAmount, ItemType},
+    recipes::RecipeId,
+    simulation::seconds_to_ticks,
+    structures::StructureType,
+};
+
+pub(super) struct TechnologyPlugin;
+
+impl Plugin for TechnologyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(DataFolderPlugin::<TechnologySet>::default())
+            .insert_resource(Technologies::default())
+            .add_systems(
+                Update,
+                update_technologies.run_if(on_event::<AssetEvent<TechnologySet>>()),
+            )
+            .register_type::<TechnologyId>();
+    }
+}
+
+/// Identifies a technology across asset files, e.g. `core::logistics`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Reflect, Deserialize)]
+#[serde(transparent)]
+pub struct TechnologyId(pub String);
+
+impl fmt::Display for TechnologyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A node of the research tree. Researching it takes `units` rounds, each consuming `cost`
+/// and then taking `unit_time` seconds.
+#[derive(Debug, Clone)]
+pub struct Technology {
+    pub id: TechnologyId,
+    pub name: String,
+    pub prerequisites: Vec<TechnologyId>,
+    pub cost: Vec<ItemAmount>,
+    pub units: u32,
+    pub unit_time: f32,
+    pub recipes: Vec<RecipeId>,
+    pub structures: Vec<StructureType>,
+}
+
+impl Technology {
+    /// How long one unit takes to study in simulation ticks at normal speed.
+    pub fn unit_ticks(&self) -> u32 {
+        seconds_to_ticks(self.unit_time)
+    }
+}
+
+/// All technologies currently loaded from the research asset files, keyed by id.
+#[derive(Resource, Default, Debug)]
+pub struct Technologies(pub HashMap<TechnologyId, Technology>);
+
+impl Technologies {
+    pub fn get(&self, id: &TechnologyId) -> Option<&Technology> {
+        self.0.get(id)
+    }
+
+    /// Whether any technology has to be researched before the recipe can be used.
+    pub fn locks_recipe(&self, recipe: &RecipeId) -> bool {
+        self.0
+            .values()
+            .any(|technology| technology.recipes.contains(recipe))
+    }
+
+    pub fn locks_structure(&self, structure: &StructureType) -> bool {
+        self.0
+            .values()
+            .any(|technology| technology.structures.contains(structure))
+    }
+}
+
+/// A single asset file of technology definitions.
+#[derive(Debug, Deserialize, TypeUuid, TypePath)]
+#[uuid = "e7b2d4f1-8c3a-4e6b-a1d9-3f7c5e2b9a40"]
+pub struct TechnologySet {
+    pub technologies: Vec<TechnologyDefinition>,
+}
+
+impl DataSet for TechnologySet {
+    type Definition = TechnologyDefinition;
+
+    const FOLDER: &'static str = "research";
+    const EXTENSIONS: &'static [&'static str] = &["research.ron"];
+
+    fn definitions(&self) -> &[TechnologyDefinition] {
+        &self.technologies
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TechnologyDefinition {
+    pub id: TechnologyId,
+    pub name: String,
+    pub prerequisites: Vec<TechnologyId>,
+    pub cost: Vec<(ItemType, u32)>,
+    pub units: u32,
+    pub unit_time: f32,
+    pub recipes: Vec<RecipeId>,
+    pub structures: Vec<StructureType>,
+}
+
+impl From<TechnologyDefinition> for Technology {
+    fn from(definition: TechnologyDefinition) -> Self {
+        Technology {
+            id: definition.id,
+            name: definition.name,
+            prerequisites: definition.prerequisites,
+            cost: definition.cost.into_iter().map(Into::into).collect(),
+            units: definition.units,
+            unit_time: definition.unit_time,
+            recipes: definition.recipes,
+            structures: definition.structures,
+        }
+    }
+}
+
+fn update_technologies(
+    technology_sets: Res<Assets<TechnologySet>>,
+    mut technologies: ResMut<Technologies>,
+) {
+    technologies.0 = loaded_definitions(&technology_sets)
+        .map(|definition| (definition.id.clone(), Technology::from(definition)))
+        .collect();
+    println!("Loaded {} technologies", technologies.0.len());
+}
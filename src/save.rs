@@ -0,0 +1,436 @@
+use core::fmt;
+use std::path::{Path, PathBuf};
+
+use bevy::{
+    prelude::*,
+    reflect::{
+        serde::{ReflectSerializer, UntypedReflectDeserializer},
+        TypeRegistryInternal,
+    },
+    utils::{HashMap, HashSet},
+};
+use serde::de::DeserializeSeed;
+
+use crate::{
+    common::{Facing, GameState, Paused},
+    crafting::{Crafter, CrafterState},
+    items::{
+        inventory::{Inventory, InventoryFilter, ItemAmount},
+        item_spawner::ItemSpawner,
+        ItemType,
+    },
+    map::{
+        generation::{LoadedChunks, WorldGenerator},
+        grid::{GridPosition, OccupiedTiles},
+        resource_nodes::ResourceNodes,
+    },
+    player::{
+        crafting_queue::{CraftingQueue, QueuedCraft},
+        Player,
+    },
+    recipes::{CraftingCategory, Recipe, RecipeId},
+    research::{technology::TechnologyId, ResearchProgress, ResearchState},
+    simulation::{TickTimer, NORMAL_SPEED},
+    structures::{
+        conveyor::Conveyor, grabber::Grabber, library::Library, smelter::Burner, spawn_structure,
+        Structure, StructureType,
+    },
+};
+
+// Bump whenever the layout of `SaveGame` changes so old files are rejected instead of misread
+const SAVE_VERSION: u32 = 1;
+const SAVE_DIRECTORY: &str = "saves";
+const QUICKSAVE_FILE: &str = "quicksave.ron";
+const AUTOSAVE_FILE: &str = "autosave.ron";
+const AUTOSAVE_SECONDS: f32 = 300.0;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                // Loading stays ungated so the main menu can load a save
+                handle_save_keys.run_if(in_state(GameState::Playing)),
+                handle_save_game.after(handle_save_keys),
+                handle_load_game.after(handle_save_keys),
+            ),
+        )
+        // Counted in simulation ticks so pausing or changing speed doesn't change how often
+        .add_systems(FixedUpdate, autosave.run_if(in_state(GameState::Playing)))
+        .insert_resource(AutosaveTimer(TickTimer::from_seconds(AUTOSAVE_SECONDS)))
+        .add_event::<SaveGameEvent>()
+        .add_event::<LoadGameEvent>()
+        // Everything reachable from a save has to be registered for it to deserialize
+        .register_type::<SaveGame>()
+        .register_type::<SavedPlayer>()
+        .register_type::<SavedStructure>()
+        .register_type::<Vec<SavedStructure>>()
+        .register_type::<TechnologyId>()
+        .register_type::<Vec<TechnologyId>>()
+        .register_type::<ResearchProgress>()
+        .register_type::<Option<ResearchProgress>>()
+        .register_type::<RecipeId>()
+        .register_type::<Recipe>()
+        .register_type::<CraftingCategory>()
+        .register_type::<Vec<CraftingCategory>>()
+        .register_type::<Option<Recipe>>()
+        .register_type::<Crafter>()
+        .register_type::<CraftingQueue>()
+        .register_type::<QueuedCraft>()
+        .register_type::<Vec<QueuedCraft>>()
+        .register_type::<CrafterState>()
+        .register_type::<Option<Crafter>>()
+        .register_type::<Inventory>()
+        .register_type::<Option<Inventory>>()
+        .register_type::<InventoryFilter>()
+        .register_type::<HashMap<ItemType, u32>>()
+        .register_type::<ItemType>()
+        .register_type::<Option<ItemType>>()
+        .register_type::<Vec<ItemType>>()
+        .register_type::<ItemAmount>()
+        .register_type::<Vec<ItemAmount>>()
+        .register_type::<Option<u32>>()
+        .register_type::<ItemSpawner>()
+        .register_type::<Option<ItemSpawner>>()
+        .register_type::<Library>()
+        .register_type::<Option<Library>>()
+        .register_type::<Burner>()
+        .register_type::<Option<Burner>>()
+        .register_type::<Grabber>()
+        .register_type::<Option<Grabber>>()
+        .register_type::<Option<TechnologyId>>()
+        .register_type::<StructureType>()
+        .register_type::<GridPosition>()
+        .register_type::<Facing>();
+    }
+}
+
+/// Writes the current factory to the given file.
+#[derive(Event, Debug, Clone)]
+pub struct SaveGameEvent(pub PathBuf);
+
+/// Replaces the current factory with the one in the given file.
+#[derive(Event, Debug, Clone)]
+pub struct LoadGameEvent(pub PathBuf);
+
+#[derive(Resource)]
+pub struct AutosaveTimer(pub TickTimer);
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(String),
+    Version(u32),
+    Invalid,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{}", error),
+            SaveError::Format(error) => write!(f, "malformed save: {}", error),
+            SaveError::Version(version) => write!(
+                f,
+                "save version {} doesn't match the current version {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::Invalid => write!(f, "save doesn't describe a factory"),
+        }
+    }
+}
+
+/// Everything needed to rebuild a factory, serialized through its `Reflect` impl.
+#[derive(Reflect, Debug, Default)]
+pub struct SaveGame {
+    pub version: u32,
+    pub seed: u64,
+    pub player: SavedPlayer,
+    pub structures: Vec<SavedStructure>,
+    pub resource_nodes: ResourceNodes,
+    pub completed_research: Vec<TechnologyId>,
+    pub current_research: Option<ResearchProgress>,
+}
+
+#[derive(Reflect, Debug, Default)]
+pub struct SavedPlayer {
+    pub translation: Vec3,
+    pub inventory: Inventory,
+    pub crafter: Crafter,
+    pub crafting_queue: CraftingQueue,
+}
+
+#[derive(Reflect, Debug)]
+pub struct SavedStructure {
+    pub structure_type: StructureType,
+    pub grid_position: GridPosition,
+    pub facing: Facing,
+    pub inventory: Option<Inventory>,
+    pub crafter: Option<Crafter>,
+    pub spawner: Option<ItemSpawner>,
+    pub conveyor_item: Option<ItemType>,
+    pub library: Option<Library>,
+    pub burner: Option<Burner>,
+    pub grabber: Option<Grabber>,
+    pub paused: bool,
+}
+
+pub fn save_path(file: &str) -> PathBuf {
+    Path::new(SAVE_DIRECTORY).join(file)
+}
+
+/// Whichever of the quicksave and autosave was written most recently.
+pub fn latest_save() -> Option<PathBuf> {
+    [QUICKSAVE_FILE, AUTOSAVE_FILE]
+        .into_iter()
+        .map(save_path)
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((modified, path))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+pub fn write_save_file(
+    path: &Path,
+    save: &SaveGame,
+    registry: &TypeRegistryInternal,
+) -> Result<(), SaveError> {
+    let serializer = ReflectSerializer::new(save, registry);
+    let contents = ron::ser::to_string_pretty(&serializer, ron::ser::PrettyConfig::default())
+        .map_err(|error| SaveError::Format(error.to_string()))?;
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory).map_err(SaveError::Io)?;
+    }
+    std::fs::write(path, contents).map_err(SaveError::Io)
+}
+
+pub fn read_save_file(path: &Path, registry: &TypeRegistryInternal) -> Result<SaveGame, SaveError> {
+    let contents = std::fs::read_to_string(path).map_err(SaveError::Io)?;
+    let mut deserializer = ron::de::Deserializer::from_str(&contents)
+        .map_err(|error| SaveError::Format(error.to_string()))?;
+    let value = UntypedReflectDeserializer::new(registry)
+        .deserialize(&mut deserializer)
+        .map_err(|error| SaveError::Format(error.to_string()))?;
+    let save = SaveGame::from_reflect(value.as_ref()).ok_or(SaveError::Invalid)?;
+    match save.version {
+        SAVE_VERSION => Ok(save),
+        version => Err(SaveError::Version(version)),
+    }
+}
+
+fn handle_save_keys(
+    keys: Res<Input<KeyCode>>,
+    mut save_events: EventWriter<SaveGameEvent>,
+    mut load_events: EventWriter<LoadGameEvent>,
+) {
+    if keys.just_pressed(KeyCode::F5) {
+        save_events.send(SaveGameEvent(save_path(QUICKSAVE_FILE)));
+    }
+    if keys.just_pressed(KeyCode::F9) {
+        load_events.send(LoadGameEvent(save_path(QUICKSAVE_FILE)));
+    }
+}
+
+fn autosave(mut timer: ResMut<AutosaveTimer>, mut save_events: EventWriter<SaveGameEvent>) {
+    if timer.0.tick(NORMAL_SPEED) {
+        save_events.send(SaveGameEvent(save_path(AUTOSAVE_FILE)));
+    }
+}
+
+// Every component of a structure that ends up in its `SavedStructure`
+type SavedStructureComponents = (
+    &'static Structure,
+    &'static GridPosition,
+    &'static Facing,
+    Option<&'static Inventory>,
+    Option<&'static Crafter>,
+    Option<&'static ItemSpawner>,
+    Option<&'static Conveyor>,
+    Option<&'static Library>,
+    Option<&'static Burner>,
+    Option<&'static Grabber>,
+    Option<&'static Paused>,
+);
+
+#[allow(clippy::too_many_arguments)]
+fn handle_save_game(
+    mut events: EventReader<SaveGameEvent>,
+    type_registry: Res<AppTypeRegistry>,
+    generator: Res<WorldGenerator>,
+    research: Res<ResearchState>,
+    nodes: Res<ResourceNodes>,
+    player: Query<(&Transform, &Inventory, &Crafter, &CraftingQueue), With<Player>>,
+    structures: Query<SavedStructureComponents, Without<Player>>,
+) {
+    let path = match events.iter().last() {
+        Some(event) => event.0.clone(),
+        None => return,
+    };
+    let (transform, inventory, crafter, crafting_queue) = player.single();
+
+    let save = SaveGame {
+        version: SAVE_VERSION,
+        seed: generator.seed,
+        player: SavedPlayer {
+            translation: transform.translation,
+            inventory: inventory.clone(),
+            crafter: crafter.clone(),
+            crafting_queue: crafting_queue.clone(),
+        },
+        structures: structures
+            .iter()
+            .map(
+                |(
+                    structure,
+                    grid_position,
+                    facing,
+                    inventory,
+                    crafter,
+                    spawner,
+                    conveyor,
+                    library,
+                    burner,
+                    grabber,
+                    paused,
+                )| {
+                    SavedStructure {
+                        structure_type: structure.0,
+                        grid_position: grid_position.clone(),
+                        facing: *facing,
+                        inventory: inventory.cloned(),
+                        crafter: crafter.cloned(),
+                        spawner: spawner.cloned(),
+                        conveyor_item: conveyor.and_then(|conveyor| conveyor.item),
+                        library: library.cloned(),
+                        burner: burner.cloned(),
+                        grabber: grabber.cloned(),
+                        paused: paused.is_some(),
+                    }
+                },
+            )
+            .collect(),
+        resource_nodes: nodes.clone(),
+        completed_research: research.completed.iter().cloned().collect(),
+        current_research: research.current.clone(),
+    };
+
+    match write_save_file(&path, &save, &type_registry.read()) {
+        Ok(()) => println!("Saved game to {:?}", path),
+        Err(error) => println!("Failed to save game to {:?}: {}", path, error),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_load_game(
+    mut commands: Commands,
+    mut events: EventReader<LoadGameEvent>,
+    type_registry: Res<AppTypeRegistry>,
+    asset_server: Res<AssetServer>,
+    mut generator: ResMut<WorldGenerator>,
+    mut loaded_chunks: ResMut<LoadedChunks>,
+    mut occupied_tiles: ResMut<OccupiedTiles>,
+    mut research: ResMut<ResearchState>,
+    mut nodes: ResMut<ResourceNodes>,
+    mut player: Query<
+        (
+            &mut Transform,
+            &mut GridPosition,
+            &mut Inventory,
+            &mut Crafter,
+            &mut CraftingQueue,
+        ),
+        With<Player>,
+    >,
+    structures: Query<Entity, With<Structure>>,
+) {
+    let path = match events.iter().last() {
+        Some(event) => event.0.clone(),
+        None => return,
+    };
+    let save = match read_save_file(&path, &type_registry.read()) {
+        Ok(save) => save,
+        Err(error) => {
+            println!("Failed to load game from {:?}: {}", path, error);
+            return;
+        }
+    };
+    println!("Loading game from {:?}", path);
+
+    // Clear out the current factory before rebuilding the saved one
+    structures
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn_recursive());
+    occupied_tiles.0.clear();
+
+    // Regenerate the map from the saved seed, the player moving below reloads nearby chunks
+    if generator.seed != save.seed {
+        generator.seed = save.seed;
+        loaded_chunks
+            .0
+            .drain()
+            .for_each(|(_, entity)| commands.entity(entity).despawn_recursive());
+    }
+
+    let (mut transform, mut grid_position, mut inventory, mut crafter, mut crafting_queue) =
+        player.single_mut();
+    transform.translation = save.player.translation;
+    *grid_position = GridPosition::from_translation(save.player.translation);
+    *inventory = save.player.inventory;
+    *crafter = save.player.crafter;
+    *crafting_queue = save.player.crafting_queue;
+    *nodes = save.resource_nodes;
+
+    for saved in save.structures {
+        let gathering_source = saved.structure_type.get_gathering_source(
+            &saved.grid_position,
+            saved.facing,
+            &generator,
+        );
+        let entity = spawn_structure(
+            &mut commands,
+            &asset_server,
+            &mut occupied_tiles,
+            saved.structure_type,
+            &saved.grid_position,
+            saved.facing,
+            gathering_source,
+        );
+        // Saved state replaces the fresh defaults the structure was spawned with
+        let mut entity_commands = commands.entity(entity);
+        if let Some(inventory) = saved.inventory {
+            entity_commands.insert(inventory);
+        }
+        if let Some(crafter) = saved.crafter {
+            entity_commands.insert(crafter);
+        }
+        if let Some(spawner) = saved.spawner {
+            entity_commands.insert(spawner);
+        }
+        if saved.conveyor_item.is_some() {
+            entity_commands.insert(Conveyor {
+                item: saved.conveyor_item,
+            });
+        }
+        if let Some(library) = saved.library {
+            entity_commands.insert(library);
+        }
+        if let Some(burner) = saved.burner {
+            entity_commands.insert(burner);
+        }
+        if let Some(grabber) = saved.grabber {
+            entity_commands.insert(grabber);
+        }
+        if saved.paused {
+            entity_commands.insert(Paused);
+        }
+    }
+
+    // Available recipes and structures follow from the restored research
+    *research = ResearchState {
+        completed: save.completed_research.into_iter().collect::<HashSet<_>>(),
+        current: save.current_research,
+    };
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+/// How many times a second the factory simulation steps in `FixedUpdate`.
+pub const TICKS_PER_SECOND: u32 = 60;
+/// Speed multipliers are whole percentages so progress can be counted in integers.
+pub const NORMAL_SPEED: u32 = 100;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(FixedTime::new(tick_duration()))
+            .register_type::<TickTimer>();
+    }
+}
+
+/// The length of one simulation tick, exact to the nanosecond so runs stay in step.
+pub fn tick_duration() -> Duration {
+    Duration::from_secs(1) / TICKS_PER_SECOND
+}
+
+/// Rounds a duration from the data files to whole ticks, at least one.
+pub fn seconds_to_ticks(seconds: f32) -> u32 {
+    (seconds * TICKS_PER_SECOND as f32).round().max(1.0) as u32
+}
+
+pub fn ticks_to_seconds(ticks: u32) -> f32 {
+    ticks as f32 / TICKS_PER_SECOND as f32
+}
+
+/// A repeating countdown measured in ticks, advanced by a percentage speed each tick.
+#[derive(Reflect, Debug, Default, Clone, PartialEq, Eq)]
+pub struct TickTimer {
+    pub duration: u32,
+    // Ticks of work at normal speed, times `NORMAL_SPEED`
+    pub elapsed: u32,
+}
+
+impl TickTimer {
+    pub fn new(duration: u32) -> Self {
+        TickTimer {
+            duration: duration.max(1),
+            elapsed: 0,
+        }
+    }
+
+    pub fn from_seconds(seconds: f32) -> Self {
+        TickTimer::new(seconds_to_ticks(seconds))
+    }
+
+    pub fn seconds(&self) -> f32 {
+        ticks_to_seconds(self.duration)
+    }
+
+    /// Changes the length of the timer, keeping how far through it has got.
+    pub fn set_seconds(&mut self, seconds: f32) {
+        let fraction = self.fraction();
+        self.duration = seconds_to_ticks(seconds);
+        self.elapsed = (fraction * (self.duration * NORMAL_SPEED) as f32) as u32;
+    }
+
+    /// Advances one tick and returns whether the timer went off, starting over if so.
+    pub fn tick(&mut self, speed: u32) -> bool {
+        self.elapsed += speed;
+        let total = self.duration * NORMAL_SPEED;
+        if self.elapsed < total {
+            return false;
+        }
+        self.elapsed -= total;
+        true
+    }
+
+    /// Advances one tick without going off, so the timer waits at its end until ticked.
+    pub fn tick_held(&mut self, speed: u32) {
+        self.elapsed = (self.elapsed + speed).min(self.duration * NORMAL_SPEED);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        (self.elapsed as f32 / (self.duration * NORMAL_SPEED) as f32).clamp(0.0, 1.0)
+    }
+
+    pub fn remaining_seconds(&self) -> f32 {
+        let remaining = (self.duration * NORMAL_SPEED).saturating_sub(self.elapsed);
+        ticks_to_seconds(remaining) / NORMAL_SPEED as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ticks until the timer first goes off
+    fn ticks_to_finish(timer: &mut TickTimer, speed: u32) -> u32 {
+        let mut ticks = 1;
+        while !timer.tick(speed) {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn speed_scales_the_ticks_taken() {
+        assert_eq!(ticks_to_finish(&mut TickTimer::new(10), NORMAL_SPEED), 10);
+        assert_eq!(ticks_to_finish(&mut TickTimer::new(10), 200), 5);
+        assert_eq!(ticks_to_finish(&mut TickTimer::new(10), 50), 20);
+        // Partial ticks round up
+        assert_eq!(ticks_to_finish(&mut TickTimer::new(10), 300), 4);
+    }
+
+    #[test]
+    fn overshoot_carries_into_the_next_round() {
+        let mut timer = TickTimer::new(2);
+        let went_off = (0..4).filter(|_| timer.tick(150)).count();
+
+        // 600 of work at 200 a round
+        assert_eq!(went_off, 3);
+        assert_eq!(timer.elapsed, 0);
+    }
+
+    #[test]
+    fn held_timer_waits_at_its_end() {
+        let mut timer = TickTimer::new(2);
+        for _ in 0..5 {
+            timer.tick_held(150);
+        }
+
+        assert_eq!(timer.fraction(), 1.0);
+        assert!(timer.tick(150));
+        assert_eq!(timer.elapsed, 150);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+
+use bevy::{ecs::entity::Entities, prelude::*, utils::HashMap};
+
+use crate::{
+    common::GameState,
+    crafting::{handle_crafting, CraftCompleteEvent, ItemsConsumedEvent},
+    items::{
+        inventory::ItemAmount,
+        item_spawner::{spawn_item_into_inventory, ItemSpawnEvent},
+        ItemType,
+    },
+    simulation::TICKS_PER_SECOND,
+    structures::{library::study_research, smelter::burn_fuel},
+};
+
+// One bucket per simulated second, enough for the longest window
+const HISTORY_SECONDS: usize = 3600;
+
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ProductionStatistics::default())
+            .add_systems(
+                FixedUpdate,
+                // Read this tick's events after they are sent, before they are cleared
+                (
+                    record_production
+                        .after(handle_crafting)
+                        .after(spawn_item_into_inventory)
+                        .after(burn_fuel)
+                        .after(study_research),
+                    advance_statistics,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The spans of time production can be looked at over.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StatisticsWindow {
+    #[default]
+    OneMinute,
+    TenMinutes,
+    OneHour,
+}
+
+impl StatisticsWindow {
+    pub const ALL: [StatisticsWindow; 3] = [
+        StatisticsWindow::OneMinute,
+        StatisticsWindow::TenMinutes,
+        StatisticsWindow::OneHour,
+    ];
+
+    pub fn seconds(&self) -> usize {
+        match self {
+            StatisticsWindow::OneMinute => 60,
+            StatisticsWindow::TenMinutes => 600,
+            StatisticsWindow::OneHour => 3600,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatisticsWindow::OneMinute => "1m",
+            StatisticsWindow::TenMinutes => "10m",
+            StatisticsWindow::OneHour => "1h",
+        }
+    }
+}
+
+/// Amounts counted per simulated second, oldest first. The last bucket is the current second.
+#[derive(Clone, Debug)]
+pub struct History {
+    buckets: VecDeque<u32>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            buckets: VecDeque::from([0]),
+        }
+    }
+}
+
+impl History {
+    pub fn add(&mut self, amount: u32) {
+        if let Some(bucket) = self.buckets.back_mut() {
+            *bucket += amount;
+        }
+    }
+
+    fn advance(&mut self) {
+        self.buckets.push_back(0);
+        if self.buckets.len() > HISTORY_SECONDS {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// The total over the last `seconds`.
+    pub fn total(&self, seconds: usize) -> u32 {
+        self.buckets.iter().rev().take(seconds).sum()
+    }
+
+    /// The average rate over the last `seconds`.
+    pub fn per_minute(&self, seconds: usize) -> f32 {
+        self.total(seconds) as f32 * 60.0 / seconds.max(1) as f32
+    }
+
+    /// Splits the window into `count` equal spans and totals each, oldest first.
+    pub fn graph(&self, window: StatisticsWindow, count: usize) -> Vec<u32> {
+        let span = (window.seconds() / count).max(1);
+        let mut values = vec![0; count];
+        for (age, amount) in self.buckets.iter().rev().take(span * count).enumerate() {
+            values[count - 1 - age / span] += amount;
+        }
+        values
+    }
+}
+
+/// How much of one item has been made and used up.
+#[derive(Clone, Debug, Default)]
+pub struct ItemStatistics {
+    pub produced: History,
+    pub consumed: History,
+}
+
+/// Rolling production figures built from crafting, gathering and consumption events,
+/// kept for an hour.
+#[derive(Resource, Default, Debug)]
+pub struct ProductionStatistics {
+    pub items: HashMap<ItemType, ItemStatistics>,
+    // What each structure (or the player, by handcrafting) has produced of each item
+    pub producers: HashMap<(Entity, ItemType), History>,
+    ticks: u32,
+}
+
+impl ProductionStatistics {
+    pub fn elapsed_seconds(&self) -> u32 {
+        self.ticks / TICKS_PER_SECOND
+    }
+
+    /// How much of the window has been recorded, counting the second in progress.
+    pub fn window_seconds(&self, window: StatisticsWindow) -> usize {
+        window.seconds().min(self.elapsed_seconds() as usize + 1)
+    }
+
+    /// Everything one structure has produced, by item.
+    pub fn produced_by(&self, entity: Entity) -> impl Iterator<Item = (&ItemType, &History)> {
+        self.producers
+            .iter()
+            .filter(move |((producer, _), _)| *producer == entity)
+            .map(|((_, item), history)| (item, history))
+    }
+
+    fn produce(&mut self, entity: Entity, amounts: &[ItemAmount]) {
+        for item_amount in amounts {
+            let amount = item_amount.amount.unwrap_or(0);
+            self.items
+                .entry(item_amount.item)
+                .or_default()
+                .produced
+                .add(amount);
+            self.producers
+                .entry((entity, item_amount.item))
+                .or_default()
+                .add(amount);
+        }
+    }
+
+    fn consume(&mut self, amounts: &[ItemAmount]) {
+        for item_amount in amounts {
+            self.items
+                .entry(item_amount.item)
+                .or_default()
+                .consumed
+                .add(item_amount.amount.unwrap_or(0));
+        }
+    }
+}
+
+// Inputs are counted as used up when the craft they went into completes
+fn record_production(
+    mut crafts: EventReader<CraftCompleteEvent>,
+    mut spawns: EventReader<ItemSpawnEvent>,
+    mut consumed: EventReader<ItemsConsumedEvent>,
+    mut statistics: ResMut<ProductionStatistics>,
+) {
+    for event in crafts.iter() {
+        statistics.produce(event.entity, &event.recipe.output);
+        statistics.consume(&event.recipe.input);
+    }
+    for event in spawns.iter() {
+        statistics.produce(event.entity, &event.items);
+    }
+    for event in consumed.iter() {
+        statistics.consume(&event.items);
+    }
+}
+
+fn advance_statistics(mut statistics: ResMut<ProductionStatistics>, entities: &Entities) {
+    statistics.ticks += 1;
+    if !statistics.ticks.is_multiple_of(TICKS_PER_SECOND) {
+        return;
+    }
+    for item in statistics.items.values_mut() {
+        item.produced.advance();
+        item.consumed.advance();
+    }
+    // Removed structures drop out of the per-structure figures
+    statistics
+        .producers
+        .retain(|(entity, _), _| entities.contains(*entity));
+    for history in statistics.producers.values_mut() {
+        history.advance();
+    }
+}
@@ -1,33 +1,21 @@
 use bevy::prelude::*;
-use bevy::render::render_phase::PhaseItem;
-use bevy::sprite::Anchor;
 
-use crate::common::Held;
-use crate::input::mouse::FaeEntityClickEvent;
-use crate::input::FaeEntityInputModifier;
+use crate::common::GameState;
+use crate::items::definitions::ItemRegistry;
 use crate::items::inventory::Inventory;
-use crate::items::ItemType;
-use crate::map::grid::GridPosition;
 use crate::player::Player;
-use crate::research::AvailableRecipes;
 use crate::{
-    common::Clickable,
-    crafting::{Crafter, CrafterState},
-    recipes::{Recipe, RecipeType},
+    crafting::{Crafter, CrafterState, CraftingCategories},
+    recipes::Recipe,
 };
 
-use super::{Structure, StructureType, STRUCTURE_Z};
-
 pub(super) struct AssemblerPlugin;
 
 impl Plugin for AssemblerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(
             Update,
-            (
-                select_assembler_recipe,
-                handle_recipe_change.after(select_assembler_recipe),
-            ),
+            handle_recipe_change.run_if(in_state(GameState::Playing)),
         )
         .add_event::<AssemblerRecipeChangedEvent>();
     }
@@ -36,108 +24,65 @@ impl Plugin for AssemblerPlugin {
 #[derive(Bundle, Default)]
 pub struct AssemblerBundle {
     pub crafter: Crafter,
+    pub categories: CraftingCategories,
     pub inventory: Inventory,
 }
 
 #[derive(Event, Debug, Reflect)]
-pub struct AssemblerRecipeChangedEvent(Entity);
-
-pub(super) fn spawn_assembler(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    position: &GridPosition,
-) {
-    println!("Spawning assembler at {:?}", position);
-    let texture = asset_server.load(StructureType::Assembler.asset_file());
-    commands
-        .spawn((
-            SpriteBundle {
-                texture,
-                transform: Transform {
-                    translation: position.sprite_translation_z(STRUCTURE_Z),
-                    ..default()
-                },
-                ..default()
-            },
-            Name::new("Assembler"),
-            Structure(StructureType::Assembler),
-            Crafter {
-                recipe: None,
-                state: CrafterState::Pending(true),
-                ..default()
-            },
-            Inventory::new(2, vec![]).filtered_for(None).clone(),
-            Clickable {},
-        ))
-        .with_children(|child_builder| {
-            child_builder.spawn(Text2dBundle {
-                text: Text::from_section(
-                    "CRFT",
-                    TextStyle {
-                        font_size: 10.0,
-                        color: Color::BLACK,
-                        ..default()
-                    },
-                ),
-                transform: Transform {
-                    translation: Vec3::new(0.0, 0.0, 1.0),
-                    ..default()
-                },
-                text_anchor: Anchor::BottomCenter,
-                ..default()
-            });
-        });
-}
+pub struct AssemblerRecipeChangedEvent(pub Entity);
 
-fn select_assembler_recipe(
-    mut click_event: EventReader<FaeEntityClickEvent>,
-    mut query: Query<&mut Crafter, Without<Player>>,
-    mut player: Query<&mut Inventory, With<Player>>,
-    available_recipes: Res<AvailableRecipes>,
-    mut recipe_change_event: EventWriter<AssemblerRecipeChangedEvent>,
-) {
-    if let Some(event) = click_event.into_iter().last() {
-        if event
-            .modifiers
-            .check_only_pressed(&vec![FaeEntityInputModifier::Shift])
-        {
-            println!("Selecting assembler recipe");
-            // TODO: Rewrite this to be fucking sane
-            event.entities.iter().for_each(|entity| {
-                if let Ok((mut crafter)) = query.get_mut(*entity) {
-                    let new_recipe = match crafter.recipe.as_ref() {
-                        Some(recipe) => {
-                            let new_recipe =
-                                recipe.recipe_type.next_available_recipe(&available_recipes);
-                            Some(new_recipe.into())
-                        }
-                        None => Some(Recipe::from(RecipeType::WoodToToy)),
-                    };
-                    match crafter.recipe.as_ref() {
-                        Some(recipe)
-                            if recipe.recipe_type
-                                == new_recipe
-                                    .as_ref()
-                                    .map_or_else(|| panic!(), |recipe| recipe.recipe_type) =>
-                        {
-                            ()
-                        }
-                        _ => {
-                            recipe_change_event.send(AssemblerRecipeChangedEvent(*entity));
-                        }
-                    }
-                    crafter.recipe = new_recipe;
-                    crafter.state = CrafterState::Pending(true);
-                }
-            });
+/// Switches a crafter over to `recipe`, emptying its old inputs if the recipe changed.
+/// A craft in progress is cancelled and its inputs go back into the crafter's inventory.
+/// Returns false, leaving the crafter alone, if it can't make the recipe, the cancelled
+/// inputs don't fit, or a finished craft is still waiting for room for its output.
+pub fn set_crafter_recipe(
+    entity: Entity,
+    crafter: &mut Crafter,
+    inventory: &mut Inventory,
+    categories: &CraftingCategories,
+    recipe: Recipe,
+    registry: &ItemRegistry,
+    recipe_change_event: &mut EventWriter<AssemblerRecipeChangedEvent>,
+) -> bool {
+    if !categories.supports(&recipe) {
+        println!("{:?} can't craft {}", entity, recipe.id);
+        return false;
+    }
+    let assembling = matches!(crafter.state, CrafterState::Assembling(_));
+    if crafter.recipe.as_ref().map(|current| &current.id) != Some(&recipe.id) {
+        if let Some(current) = crafter.recipe.as_ref().filter(|_| assembling) {
+            if crafter.is_finished(current) {
+                // The work is done, so keep it until the output has been taken out
+                println!(
+                    "{:?} is holding a finished {} craft until its output fits",
+                    entity, current.id
+                );
+                return false;
+            }
+            if !inventory.has_space_for(&current.input, registry) {
+                println!(
+                    "{:?} has no room to cancel its {} craft",
+                    entity, current.id
+                );
+                return false;
+            }
+            inventory.add_items(&current.input, registry);
         }
+        crafter.progress = 0;
+        crafter.state = CrafterState::Pending(true);
+        recipe_change_event.send(AssemblerRecipeChangedEvent(entity));
+    } else if !assembling {
+        crafter.state = CrafterState::Pending(true);
     }
+    crafter.recipe = Some(recipe);
+    true
 }
 
 fn handle_recipe_change(
     mut query: Query<(&mut Inventory, &Crafter), Without<Player>>,
     mut player: Query<&mut Inventory, With<Player>>,
     mut event: EventReader<AssemblerRecipeChangedEvent>,
+    registry: Res<ItemRegistry>,
 ) {
     let mut player_inventory = player.single_mut();
     for event in event.iter() {
@@ -145,7 +90,7 @@ fn handle_recipe_change(
             Ok((inventory, crafter)) => (inventory, crafter),
             Err(_) => continue,
         };
-        inventory.force_empty_into_other(player_inventory.as_mut());
+        inventory.force_empty_into_other(player_inventory.as_mut(), &registry);
         if let Some(recipe) = crafter.recipe.as_ref() {
             inventory.filtered_for(Some(recipe));
         }
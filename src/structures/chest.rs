@@ -2,12 +2,6 @@ use bevy::prelude::*;
 
 use crate::items::inventory::Inventory;
 
-pub struct ChestPlugin;
-
-impl Plugin for ChestPlugin {
-    fn build(&self, app: &mut App) {}
-}
-
 #[derive(Component, Reflect, Default)]
 pub struct Chest;
 
@@ -0,0 +1,296 @@
+use bevy::{
+    prelude::*,
+    sprite::Anchor,
+    utils::{HashMap, HashSet},
+};
+
+use crate::{
+    common::{Facing, GameState, Held, Holdable, Paused},
+    input::{mouse::FaeEntityClickEvent, FaeEntityInputModifier},
+    items::{
+        definitions::ItemRegistry,
+        inventory::{Inventory, ItemAmount},
+        ItemType,
+    },
+    map::grid::{GridPosition, OccupiedTiles},
+    player::Player,
+};
+
+use super::{accepts_delivery, deliver, smelter::Burner, DeliveryTarget};
+
+pub(super) struct ConveyorPlugin;
+
+impl Plugin for ConveyorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            advance_conveyors.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (handle_click_conveyor, update_conveyor_label)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .register_type::<Conveyor>();
+    }
+}
+
+/// A belt tile that carries at most one item, moving it one tile towards its `Facing` each tick.
+#[derive(Component, Reflect, Default, Debug)]
+pub struct Conveyor {
+    pub item: Option<ItemType>,
+}
+
+#[derive(Bundle, Default)]
+pub struct ConveyorBundle {
+    pub conveyor: Conveyor,
+}
+
+// Shows the item currently on the belt
+#[derive(Component)]
+pub struct ConveyorItemLabel;
+
+pub(super) fn spawn_conveyor_label(child_builder: &mut ChildBuilder, facing: Facing) {
+    child_builder.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font_size: 10.0,
+                    color: Color::BLUE,
+                    ..default()
+                },
+            ),
+            // Undo the belt's rotation so the label reads upright
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 1.0),
+                rotation: facing.rotation().inverse(),
+                ..default()
+            },
+            text_anchor: Anchor::TopCenter,
+            ..default()
+        },
+        ConveyorItemLabel,
+    ));
+}
+
+/// Snapshot of every belt at the start of a tick, used to work out which items can move.
+struct ConveyorNetwork {
+    belts: HashMap<IVec2, (Facing, Option<ItemType>)>,
+    paused: HashSet<IVec2>,
+    resolved: HashMap<IVec2, bool>,
+    visiting: HashSet<IVec2>,
+    claimed: HashSet<IVec2>,
+}
+
+impl ConveyorNetwork {
+    fn new(belts: HashMap<IVec2, (Facing, Option<ItemType>)>, paused: HashSet<IVec2>) -> Self {
+        ConveyorNetwork {
+            belts,
+            paused,
+            resolved: HashMap::default(),
+            visiting: HashSet::default(),
+            claimed: HashSet::default(),
+        }
+    }
+
+    fn target(&self, position: IVec2) -> Option<IVec2> {
+        self.belts
+            .get(&position)
+            .map(|(facing, _)| position + facing.offset())
+    }
+
+    // An item advances if the tile in front is an empty belt, a belt that is itself advancing,
+    // or an inventory that accepts the item. Otherwise it stays put and backs up the line.
+    // Paused belts hold their item and don't take new ones.
+    fn can_advance(
+        &mut self,
+        position: IVec2,
+        accepts: &mut impl FnMut(IVec2, ItemType) -> bool,
+    ) -> bool {
+        if let Some(result) = self.resolved.get(&position) {
+            return *result;
+        }
+        let (item, target) = match (self.belts.get(&position), self.target(position)) {
+            (Some((_, Some(item))), Some(target)) => (*item, target),
+            _ => {
+                self.resolved.insert(position, false);
+                return false;
+            }
+        };
+        if self.paused.contains(&position) || self.paused.contains(&target) {
+            self.resolved.insert(position, false);
+            return false;
+        }
+        if !self.visiting.insert(position) {
+            // Back where we started, so this is a closed loop of full belts that rotates as one
+            return true;
+        }
+
+        // Claims are checked after following the line, which may close a loop through `target`
+        let result = match self.belts.get(&target) {
+            Some((_, target_item)) => {
+                (target_item.is_none() || self.can_advance(target, accepts))
+                    && !self.claimed.contains(&target)
+            }
+            None => accepts(target, item),
+        };
+        if result && self.belts.contains_key(&target) {
+            self.claimed.insert(target);
+        }
+
+        self.visiting.remove(&position);
+        self.resolved.insert(position, result);
+        result
+    }
+}
+
+fn advance_conveyors(
+    mut conveyors: Query<(
+        Entity,
+        &GridPosition,
+        &Facing,
+        &mut Conveyor,
+        Option<&Paused>,
+    )>,
+    // Paused structures don't take deliveries
+    mut inventories: Query<DeliveryTarget, (Without<Player>, Without<Paused>)>,
+    occupied_tiles: Res<OccupiedTiles>,
+    registry: Res<ItemRegistry>,
+) {
+    let belt_entities: HashMap<IVec2, Entity> = conveyors
+        .iter()
+        .map(|(entity, grid, _, _, _)| (grid.0, entity))
+        .collect();
+
+    let mut network = ConveyorNetwork::new(
+        conveyors
+            .iter()
+            .map(|(_, grid, facing, conveyor, _)| (grid.0, (*facing, conveyor.item)))
+            .collect(),
+        conveyors
+            .iter()
+            .filter(|(_, _, _, _, paused)| paused.is_some())
+            .map(|(_, grid, _, _, _)| grid.0)
+            .collect(),
+    );
+    // Inventories are looked up by occupied tile so belts can feed any side of larger structures.
+    // Deliveries are tried on copies so several belts can't overfill one inventory in a tick.
+    let mut deliveries: HashMap<Entity, (Inventory, Option<Burner>)> = HashMap::default();
+    let mut accepts = |position: IVec2, item: ItemType| {
+        let entity = match occupied_tiles.get(&GridPosition(position)) {
+            Some(entity) => entity,
+            None => return false,
+        };
+        if !deliveries.contains_key(&entity) {
+            match inventories.get(entity) {
+                Ok((inventory, burner)) => {
+                    deliveries.insert(entity, (inventory.clone(), burner.cloned()));
+                }
+                Err(_) => return false,
+            }
+        }
+        let (inventory, burner) = deliveries.get_mut(&entity).unwrap();
+        let accepted = accepts_delivery(inventory, burner.as_ref(), item, &registry);
+        if accepted {
+            deliver(inventory, burner.as_mut(), item, &registry);
+        }
+        accepted
+    };
+
+    // Sort so that merging belts resolve the same way every tick
+    let mut positions: Vec<IVec2> = belt_entities.keys().copied().collect();
+    positions.sort_by_key(|position| (position.x, position.y));
+    let moves: Vec<(IVec2, IVec2)> = positions
+        .into_iter()
+        .filter_map(
+            |position| match network.can_advance(position, &mut accepts) {
+                true => network.target(position).map(|target| (position, target)),
+                false => None,
+            },
+        )
+        .collect();
+
+    // Take every moving item off its belt first so items landing on a vacated belt aren't lost
+    let moved_items: Vec<(ItemType, IVec2)> = moves
+        .into_iter()
+        .filter_map(|(position, target)| {
+            let (_, _, _, mut conveyor, _) = conveyors.get_mut(belt_entities[&position]).ok()?;
+            conveyor.item.take().map(|item| (item, target))
+        })
+        .collect();
+
+    for (item, target) in moved_items {
+        if let Some(entity) = belt_entities.get(&target) {
+            if let Ok((_, _, _, mut conveyor, _)) = conveyors.get_mut(*entity) {
+                conveyor.item = Some(item);
+            }
+        } else if let Some(entity) = occupied_tiles.get(&GridPosition(target)) {
+            if let Ok((mut inventory, burner)) = inventories.get_mut(entity) {
+                deliver(&mut inventory, burner.map(Mut::into_inner), item, &registry);
+            }
+        }
+    }
+}
+
+fn handle_click_conveyor(
+    mut event: EventReader<FaeEntityClickEvent>,
+    mut player: Query<&mut Inventory, With<Player>>,
+    mut conveyors: Query<&mut Conveyor>,
+    mut held: Query<&mut Held>,
+    registry: Res<ItemRegistry>,
+) {
+    let click_event = match event.iter().last() {
+        Some(click_event) if !click_event.entities.is_empty() => click_event,
+        _ => return,
+    };
+    let mut conveyor = match conveyors.get_mut(*click_event.entities.first().unwrap()) {
+        Ok(conveyor) => conveyor,
+        Err(_) => return,
+    };
+    let mut player_inventory = player.single_mut();
+
+    if click_event
+        .modifiers
+        .check_only_pressed(&[FaeEntityInputModifier::Ctrl])
+    {
+        // Pick the item back up off the belt
+        if let Some(item) = conveyor.item {
+            if player_inventory.space_for(&item, &registry) > 0 {
+                player_inventory.add_items(&[(item, 1).into()], &registry);
+                conveyor.item = None;
+            }
+        }
+    } else if click_event.modifiers.check_only_pressed(&[]) && conveyor.item.is_none() {
+        let mut held = held.single_mut();
+        let item = match held.0 {
+            Some(Holdable::Item(item)) => item,
+            _ => return,
+        };
+        if player_inventory.remove_items(&[(item, 1).into()]) {
+            conveyor.item = Some(item);
+        }
+        if !player_inventory.has_item(&ItemAmount {
+            item,
+            amount: Some(1),
+        }) {
+            *held = Held(None);
+        }
+    }
+}
+
+fn update_conveyor_label(
+    conveyors: Query<(&Conveyor, &Children), Changed<Conveyor>>,
+    mut labels: Query<&mut Text, With<ConveyorItemLabel>>,
+) {
+    for (conveyor, children) in &conveyors {
+        for child in children.iter() {
+            if let Ok(mut text) = labels.get_mut(*child) {
+                text.sections[0].value = conveyor
+                    .item
+                    .map_or_else(String::new, |item| item.to_string());
+            }
+        }
+    }
+}
@@ -1,87 +1,79 @@
-use bevy::{prelude::*, sprite::Anchor};
+use bevy::prelude::*;
 
 use crate::{
-    common::Clickable,
-    items::{inventory::Inventory, item_spawner::ItemSpawner, ItemType},
-    map::grid::GridPosition,
-    structures::{Structure, StructureType, STRUCTURE_Z},
+    common::Facing,
+    items::{
+        inventory::Inventory,
+        item_spawner::{ItemSpawnSource, ItemSpawner},
+        ItemType,
+    },
+    map::{
+        generation::WorldGenerator,
+        grid::{GridPosition, TileType},
+    },
+    structures::StructureType,
 };
 
-pub struct GathererStructurePlugin;
-
 impl StructureType {
     pub fn get_gathering_spawner(&self) -> Option<ItemSpawner> {
-        use ItemType::*;
         use StructureType::*;
         match self {
-            WoodFairy => Some(ItemSpawner::new(vec![(Wood, 1).into()], 10.0)),
-            StoneFairy => Some(ItemSpawner::new(vec![(Stone, 1).into()], 10.0)),
-            CrystalFairy => Some(ItemSpawner::new(vec![(Crystal, 1).into()], 15.0)),
+            WoodFairy => Some(ItemSpawner::new(vec![(ItemType::WOOD, 1).into()], 10.0)),
+            StoneFairy => Some(ItemSpawner::new(vec![(ItemType::STONE, 1).into()], 10.0)),
+            CrystalFairy => Some(ItemSpawner::new(vec![(ItemType::CRYSTAL, 1).into()], 15.0)),
             _ => None,
         }
     }
+
+    /// The tile a gatherer needs to be on or next to in order to gather.
+    pub fn resource_tile(&self) -> Option<TileType> {
+        use StructureType::*;
+        match self {
+            WoodFairy => Some(TileType::Forest),
+            StoneFairy => Some(TileType::Stone),
+            CrystalFairy => Some(TileType::Crystal),
+            _ => None,
+        }
+    }
+
+    /// The structure's footprint along with every tile bordering it.
+    pub fn gathering_tiles(&self, anchor: &GridPosition, facing: Facing) -> Vec<GridPosition> {
+        let footprint = self.rotated_footprint(facing);
+        (-1..=footprint.x)
+            .flat_map(|x| (-1..=footprint.y).map(move |y| IVec2::new(x, y)))
+            // Skip the diagonal corners, only tiles sharing an edge count as adjacent
+            .filter(|offset| {
+                let outside_x = offset.x < 0 || offset.x >= footprint.x;
+                let outside_y = offset.y < 0 || offset.y >= footprint.y;
+                !(outside_x && outside_y)
+            })
+            .map(|offset| GridPosition(anchor.0 + offset))
+            .collect()
+    }
+
+    /// The resource tiles a gatherer placed at `anchor` draws from, if there are any.
+    pub fn get_gathering_source(
+        &self,
+        anchor: &GridPosition,
+        facing: Facing,
+        generator: &WorldGenerator,
+    ) -> Option<ItemSpawnSource> {
+        let resource_tile = self.resource_tile()?;
+        let tiles: Vec<GridPosition> = self
+            .gathering_tiles(anchor, facing)
+            .into_iter()
+            .filter(|tile| generator.tile_at(tile) == resource_tile)
+            .collect();
+        if tiles.is_empty() {
+            return None;
+        }
+        Some(ItemSpawnSource { tiles })
+    }
 }
 
 #[derive(Bundle, Default)]
 pub struct GathererBundle {
     pub spawner: ItemSpawner,
+    pub source: ItemSpawnSource,
     pub inventory: Inventory,
 }
-
-impl Plugin for GathererStructurePlugin {
-    fn build(&self, app: &mut App) {}
-}
-
-pub fn spawn_gatherer_structure(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    structure_type: StructureType,
-    mouse_grid: &GridPosition,
-) {
-    use StructureType::{CrystalFairy, StoneFairy, WoodFairy};
-    println!("Spawning gatherer at {:?}", mouse_grid);
-    let texture = asset_server.load(structure_type.asset_file());
-    let (name, display_text) = match structure_type {
-        WoodFairy => ("Wood Fairy", "WOOD"),
-        StoneFairy => ("Stone Fairy", "STONE"),
-        CrystalFairy => ("Crystal Fairy", "CRYSTAL"),
-        _ => return,
-    };
-
-    commands
-        .spawn((
-            SpriteBundle {
-                texture,
-                transform: Transform {
-                    translation: mouse_grid.sprite_translation_z(STRUCTURE_Z),
-                    ..default()
-                },
-                ..default()
-            },
-            Name::new(name),
-            Structure(structure_type),
-            mouse_grid.clone(),
-            structure_type.get_gathering_spawner().unwrap(),
-            Inventory::new(2, vec![]).filtered_only_remove().clone(),
-            Clickable {},
-        ))
-        .with_children(|child_builder| {
-            // Add the text section to the gatherer based on the type
-            child_builder.spawn(Text2dBundle {
-                text: Text::from_section(
-                    display_text,
-                    TextStyle {
-                        font_size: 10.0,
-                        color: Color::BLACK,
-                        ..default()
-                    },
-                ),
-                transform: Transform {
-                    translation: Vec3::new(0.0, 0.0, 1.0),
-                    ..default()
-                },
-                text_anchor: Anchor::BottomCenter,
-                ..default()
-            });
-        });
-}
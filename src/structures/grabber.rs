@@ -0,0 +1,167 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{Facing, GameState, Paused},
+    items::{definitions::ItemRegistry, ItemType},
+    map::grid::{GridPosition, OccupiedTiles},
+    player::Player,
+    simulation::{TickTimer, NORMAL_SPEED},
+};
+
+use super::{accepts_delivery, conveyor::Conveyor, deliver, DeliveryTarget};
+
+const DEFAULT_SWING_SECONDS: f32 = 1.0;
+
+pub(super) struct GrabberPlugin;
+
+impl Plugin for GrabberPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            swing_grabbers.run_if(in_state(GameState::Playing)),
+        )
+        .register_type::<Grabber>();
+    }
+}
+
+/// An arm that moves one item per swing from the tile behind it to the tile it is facing.
+/// The timer's length is the swing time.
+#[derive(Component, Reflect, Debug, Clone)]
+pub struct Grabber {
+    pub timer: TickTimer,
+}
+
+impl Grabber {
+    pub fn new(swing_seconds: f32) -> Self {
+        Grabber {
+            timer: TickTimer::from_seconds(swing_seconds),
+        }
+    }
+
+    pub fn swing_seconds(&self) -> f32 {
+        self.timer.seconds()
+    }
+
+    pub fn set_swing_seconds(&mut self, swing_seconds: f32) {
+        self.timer.set_seconds(swing_seconds);
+    }
+}
+
+impl Default for Grabber {
+    fn default() -> Self {
+        Grabber::new(DEFAULT_SWING_SECONDS)
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct GrabberBundle {
+    pub grabber: Grabber,
+}
+
+// Anything a grabber can take from or put into
+#[derive(Clone, Copy)]
+enum GrabberEnd {
+    Inventory(Entity),
+    Conveyor(Entity),
+}
+
+fn swing_grabbers(
+    mut grabbers: Query<(&GridPosition, &Facing, &mut Grabber), Without<Paused>>,
+    mut inventories: Query<DeliveryTarget, Without<Player>>,
+    mut conveyors: Query<&mut Conveyor>,
+    paused: Query<(), With<Paused>>,
+    occupied_tiles: Res<OccupiedTiles>,
+    registry: Res<ItemRegistry>,
+) {
+    for (grid, facing, mut grabber) in &mut grabbers {
+        if !grabber.timer.tick(NORMAL_SPEED) {
+            continue;
+        }
+        // Paused structures and belts neither give nor take items, same as for conveyors
+        let end_at = |tile: IVec2| {
+            let entity = occupied_tiles.get(&GridPosition(tile))?;
+            if paused.contains(entity) {
+                None
+            } else if inventories.contains(entity) {
+                Some(GrabberEnd::Inventory(entity))
+            } else if conveyors.contains(entity) {
+                Some(GrabberEnd::Conveyor(entity))
+            } else {
+                None
+            }
+        };
+        let (source, target) = match (
+            end_at(grid.0 - facing.offset()),
+            end_at(grid.0 + facing.offset()),
+        ) {
+            (Some(source), Some(target)) => (source, target),
+            _ => continue,
+        };
+
+        let candidates: Vec<ItemType> = match source {
+            GrabberEnd::Inventory(entity) => inventories
+                .get(entity)
+                .map(|(inventory, _)| {
+                    inventory
+                        .pullable_items(
+                            inventory
+                                .items
+                                .iter()
+                                .map(|(item, amount)| (*item, *amount).into())
+                                .collect(),
+                        )
+                        .into_iter()
+                        .map(|item_amount| item_amount.item)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            GrabberEnd::Conveyor(entity) => conveyors
+                .get(entity)
+                .ok()
+                .and_then(|conveyor| conveyor.item)
+                .into_iter()
+                .collect(),
+        };
+
+        let item = candidates.into_iter().find(|item| match target {
+            GrabberEnd::Inventory(entity) => {
+                inventories.get(entity).is_ok_and(|(inventory, burner)| {
+                    accepts_delivery(inventory, burner, *item, &registry)
+                })
+            }
+            GrabberEnd::Conveyor(entity) => conveyors
+                .get(entity)
+                .is_ok_and(|conveyor| conveyor.item.is_none()),
+        });
+        let item = match item {
+            Some(item) => item,
+            None => continue,
+        };
+
+        // Take the item out of the source first so a failed pull never duplicates it
+        let taken = match source {
+            GrabberEnd::Inventory(entity) => inventories
+                .get_mut(entity)
+                .is_ok_and(|(mut inventory, _)| inventory.remove_items(&[(item, 1).into()])),
+            GrabberEnd::Conveyor(entity) => conveyors
+                .get_mut(entity)
+                .is_ok_and(|mut conveyor| conveyor.item.take().is_some()),
+        };
+        if !taken {
+            continue;
+        }
+
+        match target {
+            GrabberEnd::Inventory(entity) => {
+                if let Ok((mut inventory, burner)) = inventories.get_mut(entity) {
+                    deliver(&mut inventory, burner.map(Mut::into_inner), item, &registry);
+                }
+            }
+            GrabberEnd::Conveyor(entity) => {
+                if let Ok(mut conveyor) = conveyors.get_mut(entity) {
+                    conveyor.item = Some(item);
+                }
+            }
+        }
+    }
+}
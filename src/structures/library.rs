@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{GameState, Paused},
+    crafting::{CrafterSpeed, ItemsConsumedEvent},
+    items::{definitions::ItemRegistry, inventory::Inventory},
+    research::{
+        technology::{Technologies, TechnologyId},
+        ResearchState,
+    },
+    simulation::NORMAL_SPEED,
+};
+
+pub(super) struct LibraryPlugin;
+
+impl Plugin for LibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            study_research.run_if(in_state(GameState::Playing)),
+        )
+        .register_type::<Library>();
+    }
+}
+
+/// A fairy library studies units of the current research, each paid for with the
+/// technology's cost out of its own inventory.
+#[derive(Component, Reflect, Default, Debug, Clone)]
+pub struct Library {
+    pub technology: Option<TechnologyId>,
+    // Ticks of study at normal speed, times `NORMAL_SPEED`
+    pub progress: u32,
+    pub studying: bool,
+}
+
+#[derive(Bundle)]
+pub struct LibraryBundle {
+    pub library: Library,
+    pub inventory: Inventory,
+}
+
+impl Default for LibraryBundle {
+    fn default() -> Self {
+        let mut inventory = Inventory::new(4, vec![]);
+        // Nothing can go in until there is research to study
+        inventory.filtered_only_remove();
+        LibraryBundle {
+            library: Library::default(),
+            inventory,
+        }
+    }
+}
+
+// Every library studies on its own, so more libraries research faster.
+// A unit's cost counts as used up once the unit is studied, as it can be refunded until then.
+pub fn study_research(
+    technologies: Res<Technologies>,
+    mut research: ResMut<ResearchState>,
+    mut libraries: Query<(
+        &mut Library,
+        &mut Inventory,
+        Option<&CrafterSpeed>,
+        Option<&Paused>,
+    )>,
+    mut consumed: EventWriter<ItemsConsumedEvent>,
+    registry: Res<ItemRegistry>,
+) {
+    let technology = research.current_technology(&technologies);
+    let current = technology.map(|technology| technology.id.clone());
+    let mut units_studied = 0;
+    // Units already being studied count too, paused or not, so libraries never pay for more
+    // than are needed
+    let completed = research
+        .current
+        .as_ref()
+        .map_or(0, |progress| progress.units_completed);
+    let started = libraries
+        .iter()
+        .filter(|(library, _, _, _)| library.studying && library.technology == current)
+        .count() as u32;
+    let mut units_left = technology.map_or(0, |technology| {
+        technology.units.saturating_sub(completed + started)
+    });
+
+    for (mut library, mut inventory, speed, paused) in &mut libraries {
+        if paused.is_some() {
+            continue;
+        }
+        if library.technology != current {
+            // Give back the cost of a unit started for other research
+            let started = library
+                .technology
+                .as_ref()
+                .filter(|_| library.studying)
+                .and_then(|id| technologies.get(id));
+            if let Some(started) = started {
+                // Keep the unit pending until its cost fits back in
+                if !inventory.has_space_for(&started.cost, &registry) {
+                    continue;
+                }
+                inventory.add_items(&started.cost, &registry);
+            }
+            library.technology = current.clone();
+            library.progress = 0;
+            library.studying = false;
+            match technology {
+                Some(technology) => {
+                    inventory.filtered_for_inputs(&technology.cost);
+                }
+                None => {
+                    inventory.filtered_only_remove();
+                }
+            }
+        }
+        let Some(technology) = technology else {
+            continue;
+        };
+
+        if !library.studying {
+            if units_left == 0 || !inventory.remove_items(&technology.cost) {
+                continue;
+            }
+            units_left -= 1;
+            library.studying = true;
+        }
+
+        let study_speed = speed.map_or(NORMAL_SPEED, |s| s.0);
+        library.progress += study_speed;
+        if library.progress >= technology.unit_ticks() * NORMAL_SPEED {
+            library.progress = 0;
+            library.studying = false;
+            units_studied += 1;
+            consumed.send(ItemsConsumedEvent {
+                items: technology.cost.clone(),
+            });
+        }
+    }
+
+    if units_studied > 0 {
+        if let Some(progress) = research.current.as_mut() {
+            progress.units_completed += units_studied;
+        }
+    }
+}
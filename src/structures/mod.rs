@@ -1,32 +1,50 @@
 use core::fmt;
 
 use bevy::{prelude::*, sprite::Anchor};
+use serde::Deserialize;
+use strum_macros::EnumIter;
 
 use crate::{
-    common::{round_to_grid, Clickable, Held, Holdable, Hoverable},
-    input::{
-        mouse::{FaeEntityClickEvent, FaeEntityContextClickEvent},
-        MyWorldCoords,
-    },
+    common::{Clickable, Facing, GameState, Held, HeldFacing, Holdable, Hoverable, Paused},
+    crafting::{Crafter, CrafterState, CraftingCategories},
+    input::mouse::{FaeEntityClickEvent, FaeEntityContextClickEvent},
     items::{
+        definitions::ItemRegistry,
         inventory::{Inventory, ItemAmount},
+        item_spawner::ItemSpawnSource,
         ItemType,
     },
-    map::grid::{GridPosition, HoveredGrid},
+    map::{
+        generation::WorldGenerator,
+        grid::{GridPosition, HoveredGrid, OccupiedTiles},
+    },
     player::Player,
+    recipes::CraftingCategory,
+    research::AvailableStructures,
     structures::{
-        assembler::{spawn_assembler, AssemblerBundle},
+        assembler::AssemblerBundle,
         chest::ChestBundle,
-        gatherer::{spawn_gatherer_structure, GathererBundle},
+        conveyor::{spawn_conveyor_label, ConveyorBundle},
+        gatherer::GathererBundle,
+        grabber::GrabberBundle,
+        library::LibraryBundle,
+        smelter::{Burner, SmelterBundle},
     },
 };
 
-use self::assembler::AssemblerPlugin;
+use self::{
+    assembler::AssemblerPlugin, conveyor::ConveyorPlugin, grabber::GrabberPlugin,
+    library::LibraryPlugin, smelter::SmelterPlugin, status::StructureStatusPlugin,
+};
 
 pub mod assembler;
 pub mod chest;
+pub mod conveyor;
 pub mod gatherer;
 pub mod grabber;
+pub mod library;
+pub mod smelter;
+pub mod status;
 
 const STRUCTURE_Z: f32 = 1.0;
 
@@ -34,10 +52,21 @@ pub struct StructurePlugin;
 
 impl Plugin for StructurePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(AssemblerPlugin)
-            .add_systems(Update, (handle_spawn_structure, handle_remove_structure))
-            .register_type::<Structure>()
-            .register_type::<StructureType>();
+        app.add_plugins((
+            AssemblerPlugin,
+            ConveyorPlugin,
+            GrabberPlugin,
+            LibraryPlugin,
+            SmelterPlugin,
+            StructureStatusPlugin,
+        ))
+        .add_systems(
+            Update,
+            (handle_spawn_structure, handle_remove_structure).run_if(in_state(GameState::Playing)),
+        )
+        .register_type::<Structure>()
+        .register_type::<StructureType>()
+        .register_type::<Paused>();
     }
 }
 
@@ -48,11 +77,12 @@ pub struct Structure(pub StructureType);
 pub struct StructureBundle {
     pub structure: Structure,
     pub grid_position: GridPosition,
+    pub facing: Facing,
     pub clickable: Clickable,
     pub hoverable: Hoverable,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Default)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect, Default, Deserialize, EnumIter)]
 pub enum StructureType {
     #[default]
     Assembler,
@@ -62,27 +92,54 @@ pub enum StructureType {
     WoodFairy,
     StoneFairy,
     CrystalFairy,
+    Library,
+    Enchanter,
+    Smelter,
 }
 
 impl StructureType {
     fn get_cost(&self) -> Vec<ItemAmount> {
-        use ItemType::*;
         use StructureType::*;
         match self {
-            Assembler => vec![(Crystal, 3).into(), (Wood, 3).into()],
-            Conveyor => vec![(Crystal, 1).into(), (Wood, 1).into(), (Stone, 1).into()],
-            Chest => vec![(Stone, 5).into()],
-            Grabber => vec![(Crystal, 2).into()],
-            WoodFairy => vec![(Toy, 2).into()],
-            StoneFairy => vec![(Toy, 3).into()],
-            CrystalFairy => vec![(Toy, 5).into()],
+            Assembler => vec![
+                (ItemType::PLANK, 4).into(),
+                (ItemType::STONE_BRICK, 2).into(),
+                (ItemType::POLISHED_CRYSTAL, 2).into(),
+            ],
+            Conveyor => vec![(ItemType::PLANK, 1).into(), (ItemType::STONE, 1).into()],
+            Chest => vec![
+                (ItemType::PLANK, 2).into(),
+                (ItemType::STONE_BRICK, 2).into(),
+            ],
+            Grabber => vec![
+                (ItemType::PLANK, 1).into(),
+                (ItemType::POLISHED_CRYSTAL, 1).into(),
+            ],
+            WoodFairy => vec![(ItemType::TOY, 2).into(), (ItemType::FAE_DUST, 2).into()],
+            StoneFairy => vec![(ItemType::TOY, 3).into(), (ItemType::FAE_DUST, 3).into()],
+            CrystalFairy => vec![
+                (ItemType::TOY, 3).into(),
+                (ItemType::POLISHED_CRYSTAL, 2).into(),
+                (ItemType::FAE_DUST, 5).into(),
+            ],
+            Library => vec![
+                (ItemType::PLANK, 5).into(),
+                (ItemType::STONE_BRICK, 5).into(),
+            ],
+            Enchanter => vec![
+                (ItemType::PLANK, 4).into(),
+                (ItemType::POLISHED_CRYSTAL, 4).into(),
+                (ItemType::FAE_DUST, 5).into(),
+            ],
+            Smelter => vec![
+                (ItemType::STONE_BRICK, 6).into(),
+                (ItemType::STONE, 4).into(),
+            ],
         }
     }
 
     fn name(&self) -> String {
-        match self {
-            _ => format!("{:?}", self),
-        }
+        format!("{:?}", self)
     }
 
     fn debug_marker(&self) -> &'static str {
@@ -95,14 +152,64 @@ impl StructureType {
             WoodFairy => "WOOD",
             StoneFairy => "STNE",
             CrystalFairy => "CSTL",
+            Library => "LIBR",
+            Enchanter => "ENCH",
+            Smelter => "SMLT",
         }
     }
 
-    pub fn asset_file(&self) -> String {
+    /// The kinds of recipe the structure can craft, empty if it doesn't craft.
+    pub fn crafting_categories(&self) -> Vec<CraftingCategory> {
+        use StructureType::*;
+        match self {
+            Assembler => vec![CraftingCategory::Assembler],
+            Enchanter => vec![CraftingCategory::Enchanter],
+            Smelter => vec![CraftingCategory::Smelter],
+            _ => vec![],
+        }
+    }
+
+    /// Size in tiles when placed facing `Right`.
+    pub fn footprint(&self) -> IVec2 {
+        use StructureType::*;
         match self {
-            _ => format!("building_{}.png", GridPosition::PIXELS_PER_TILE),
+            Assembler => IVec2::new(2, 2),
+            _ => IVec2::ONE,
         }
     }
+
+    pub fn rotated_footprint(&self, facing: Facing) -> IVec2 {
+        let footprint = self.footprint();
+        match facing {
+            Facing::Left | Facing::Right => footprint,
+            Facing::Top | Facing::Bottom => IVec2::new(footprint.y, footprint.x),
+        }
+    }
+
+    /// Every tile covered when the structure's bottom-left corner is at `anchor`.
+    pub fn occupied_tiles(&self, anchor: &GridPosition, facing: Facing) -> Vec<GridPosition> {
+        let footprint = self.rotated_footprint(facing);
+        (0..footprint.x)
+            .flat_map(|x| (0..footprint.y).map(move |y| IVec2::new(x, y)))
+            .map(|offset| GridPosition(anchor.0 + offset))
+            .collect()
+    }
+
+    /// Translation that centers the sprite over the whole footprint.
+    pub fn sprite_translation_z(&self, anchor: &GridPosition, facing: Facing, z: f32) -> Vec3 {
+        let centering = (self.rotated_footprint(facing) - IVec2::ONE).as_vec2()
+            * GridPosition::PIXELS_PER_TILE as f32
+            / 2.0;
+        anchor.sprite_translation_z(z) + centering.extend(0.0)
+    }
+
+    pub fn sprite_size(&self) -> Vec2 {
+        self.footprint().as_vec2() * GridPosition::PIXELS_PER_TILE as f32
+    }
+
+    pub fn asset_file(&self) -> String {
+        format!("building_{}.png", GridPosition::PIXELS_PER_TILE)
+    }
 }
 
 impl fmt::Display for StructureType {
@@ -116,21 +223,29 @@ impl fmt::Display for StructureType {
             WoodFairy => write!(f, "wood-fairy"),
             StoneFairy => write!(f, "stone-fairy"),
             CrystalFairy => write!(f, "crystal-fairy"),
+            Library => write!(f, "fairy-library"),
+            Enchanter => write!(f, "enchanter"),
+            Smelter => write!(f, "smelter"),
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_spawn_structure(
     mut commands: Commands,
     mut event: EventReader<FaeEntityClickEvent>,
     mouse_grid: Res<HoveredGrid>,
     mut query: Query<(&mut Inventory, &GridPosition), With<Player>>,
     mut selected_structure: Query<&mut Held>,
+    held_facing: Res<HeldFacing>,
+    mut occupied_tiles: ResMut<OccupiedTiles>,
+    generator: Res<WorldGenerator>,
     asset_server: Res<AssetServer>,
+    available_structures: Res<AvailableStructures>,
 ) {
     let mut selected_structure = selected_structure.single_mut();
     if let Some(click_event) = event.iter().last() {
-        if click_event.entities.len() > 0 {
+        if !click_event.entities.is_empty() {
             // If this tile is occupied, don't spawn a structure.
             // TODO: Query the tile to see if it can support a structure?
             return;
@@ -142,74 +257,163 @@ fn handle_spawn_structure(
             Some(Holdable::Structure(structure_type)) => structure_type,
             _ => return,
         };
+        if !available_structures.0.contains(&structure_type) {
+            println!("{:?} hasn't been researched yet", structure_type);
+            return;
+        }
+        let tiles = structure_type.occupied_tiles(&mouse_grid.0, held_facing.0);
+        if !occupied_tiles.is_free(&tiles) {
+            return;
+        }
+        // Gatherers only work on or next to the resource they gather
+        let gathering_source =
+            structure_type.get_gathering_source(&mouse_grid.0, held_facing.0, &generator);
+        if structure_type.resource_tile().is_some() && gathering_source.is_none() {
+            println!(
+                "No {:?} nearby for {:?}",
+                structure_type.resource_tile(),
+                structure_type
+            );
+            return;
+        }
         println!(
             "Spawning structure {:?} for {:?}",
             structure_type,
             structure_type.get_cost()
         );
         if inventory.remove_items(&structure_type.get_cost()) {
-            use StructureType::*;
-            let mut structure_commands = commands.spawn((
-                StructureBundle {
-                    structure: Structure(structure_type),
-                    grid_position: mouse_grid.grid_position.clone(),
+            spawn_structure(
+                &mut commands,
+                &asset_server,
+                &mut occupied_tiles,
+                structure_type,
+                &mouse_grid.0,
+                held_facing.0,
+                gathering_source,
+            );
+            *selected_structure = Held(None);
+        }
+    }
+}
+
+/// Spawns a placed structure with its behavior and marks its footprint as occupied.
+pub fn spawn_structure(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    occupied_tiles: &mut OccupiedTiles,
+    structure_type: StructureType,
+    grid_position: &GridPosition,
+    facing: Facing,
+    gathering_source: Option<ItemSpawnSource>,
+) -> Entity {
+    use StructureType::*;
+    let mut structure_commands = commands.spawn((
+        StructureBundle {
+            structure: Structure(structure_type),
+            grid_position: grid_position.clone(),
+            facing,
+            ..default()
+        },
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(structure_type.sprite_size()),
+                ..default()
+            },
+            transform: Transform {
+                translation: structure_type.sprite_translation_z(
+                    grid_position,
+                    facing,
+                    STRUCTURE_Z,
+                ),
+                rotation: facing.rotation(),
+                ..default()
+            },
+            texture: asset_server.load(structure_type.asset_file()),
+            ..default()
+        },
+        Name::from(structure_type.name()),
+    ));
+    // Add the debug marker text to identify the structure, kept upright however it faces.
+    let upright = facing.rotation().inverse();
+    structure_commands.with_children(|child_builder| {
+        child_builder.spawn(Text2dBundle {
+            text: Text::from_section(
+                structure_type.debug_marker(),
+                TextStyle {
+                    font_size: 10.0,
+                    color: Color::BLACK,
                     ..default()
                 },
-                SpriteBundle {
-                    transform: Transform {
-                        translation: mouse_grid.grid_position.sprite_translation_z(STRUCTURE_Z),
-                        ..default()
-                    },
-                    texture: asset_server.load(structure_type.asset_file()),
+            ),
+            transform: Transform {
+                translation: Vec3::new(0.0, 0.0, 1.0),
+                rotation: upright,
+                ..default()
+            },
+            text_anchor: Anchor::BottomCenter,
+            ..default()
+        });
+    });
+    match structure_type {
+        Assembler | Enchanter => {
+            structure_commands.insert(AssemblerBundle {
+                categories: CraftingCategories(structure_type.crafting_categories()),
+                ..default()
+            });
+        }
+        Smelter => {
+            structure_commands.insert((
+                AssemblerBundle {
+                    categories: CraftingCategories(structure_type.crafting_categories()),
                     ..default()
                 },
-                Name::from(structure_type.name()),
+                SmelterBundle::default(),
             ));
-            // Add the debug marker text to identify the structure.
-            structure_commands.with_children(|child_builder| {
-                child_builder.spawn(Text2dBundle {
-                    text: Text::from_section(
-                        structure_type.debug_marker(),
-                        TextStyle {
-                            font_size: 10.0,
-                            color: Color::BLACK,
-                            ..default()
-                        },
-                    ),
-                    transform: Transform {
-                        translation: Vec3::new(0.0, 0.0, 1.0),
-                        ..default()
-                    },
-                    text_anchor: Anchor::BottomCenter,
-                    ..default()
-                });
+        }
+        Chest => {
+            structure_commands.insert(ChestBundle::default());
+        }
+        Conveyor => {
+            structure_commands
+                .insert(ConveyorBundle::default())
+                .with_children(|child_builder| spawn_conveyor_label(child_builder, facing));
+        }
+        Grabber => {
+            structure_commands.insert(GrabberBundle::default());
+        }
+        Library => {
+            structure_commands.insert(LibraryBundle::default());
+        }
+        WoodFairy | StoneFairy | CrystalFairy => {
+            structure_commands.insert(GathererBundle {
+                spawner: structure_type.get_gathering_spawner().unwrap(),
+                source: gathering_source.unwrap_or_default(),
+                ..default()
             });
-            match structure_type {
-                Assembler => {
-                    structure_commands.insert(AssemblerBundle::default());
-                }
-                Chest => {
-                    structure_commands.insert(ChestBundle::default());
-                }
-                WoodFairy | StoneFairy | CrystalFairy => {
-                    structure_commands.insert(GathererBundle {
-                        spawner: structure_type.get_gathering_spawner().unwrap(),
-                        ..default()
-                    });
-                }
-                _ => (),
-            }
-            *selected_structure = Held(None);
         }
     }
+    let entity = structure_commands.id();
+    occupied_tiles.occupy(structure_type.occupied_tiles(grid_position, facing), entity);
+    entity
 }
 
+// Everything a removed structure refunds to the player
+type RemovableStructure = (
+    &'static Structure,
+    Option<&'static mut Inventory>,
+    Option<&'static mut conveyor::Conveyor>,
+    Option<&'static Burner>,
+    Option<&'static Crafter>,
+);
+
 fn handle_remove_structure(
     mut commands: Commands,
     mut event: EventReader<FaeEntityContextClickEvent>,
     mut query: Query<(&mut Inventory, &GridPosition), With<Player>>,
     mut selected_structure: Query<&mut Held>,
-    mut structure: Query<(&Structure, Option<&mut Inventory>), (With<Clickable>, Without<Player>)>,
+    mut occupied_tiles: ResMut<OccupiedTiles>,
+    mut structure: Query<RemovableStructure, (With<Clickable>, Without<Player>)>,
+    registry: Res<ItemRegistry>,
 ) {
     if let Some(Holdable::Item(_)) = selected_structure.single_mut().0 {
         // If we're holding an item, we don't want to remove a structure.
@@ -218,19 +422,73 @@ fn handle_remove_structure(
 
     if let Some(event) = event.iter().last() {
         println!("Handling remove structure");
-        if !event.modifiers.check_only_pressed(&vec![]) || event.entities.is_empty() {
+        if !event.modifiers.check_only_pressed(&[]) || event.entities.is_empty() {
             return;
         }
 
         let entity = event.entities.first().unwrap();
         let (mut player_inventory, _player_grid) = query.single_mut();
-        if let Ok((structure, mut structure_inventory)) = structure.get_mut(*entity) {
-            player_inventory.add_items(&structure.0.get_cost());
-            if let Some(structure_inventory) = structure_inventory.as_mut() {
-                structure_inventory.force_empty_into_other(player_inventory.as_mut());
+        if let Ok((structure, structure_inventory, conveyor, burner, crafter)) =
+            structure.get_mut(*entity)
+        {
+            // Refund the cost along with everything inside, but only if it all fits
+            let mut refund = structure.0.get_cost();
+            if let Some(structure_inventory) = structure_inventory {
+                refund.extend(
+                    structure_inventory
+                        .items
+                        .iter()
+                        .map(|(item, amount)| ItemAmount::from((*item, *amount))),
+                );
+            }
+            if let Some(item) = conveyor.and_then(|conveyor| conveyor.item) {
+                refund.push((item, 1).into());
             }
+            refund.extend(burner.and_then(Burner::stored_fuel));
+            // Inputs already taken for the craft in progress come back too
+            if let Some(crafter) = crafter {
+                if let (CrafterState::Assembling(_), Some(recipe)) =
+                    (&crafter.state, crafter.recipe.as_ref())
+                {
+                    refund.extend(recipe.input.iter().copied());
+                }
+            }
+            if !player_inventory.has_space_for(&refund, &registry) {
+                println!("Not enough space to pick up {:?}", structure.0);
+                return;
+            }
+            player_inventory.add_items(&refund, &registry);
             // Remove the structure that was clicked and its descendent entities to clear text
+            occupied_tiles.free(*entity);
             commands.entity(*entity).despawn_recursive();
         }
     }
 }
+
+/// Where a structure takes deliveries: its inventory and, if it burns fuel, the fuel slot.
+pub type DeliveryTarget = (&'static mut Inventory, Option<&'static mut Burner>);
+
+/// Whether a structure takes the item, either as fuel for its burner or into its inventory.
+pub fn accepts_delivery(
+    inventory: &Inventory,
+    burner: Option<&Burner>,
+    item: ItemType,
+    registry: &ItemRegistry,
+) -> bool {
+    burner.is_some_and(|burner| burner.accepts(&item)) || inventory.can_add_items(&[item], registry)
+}
+
+/// Hands a single item to a structure, filling its burner's fuel slot before its inventory.
+pub fn deliver(
+    inventory: &mut Inventory,
+    burner: Option<&mut Burner>,
+    item: ItemType,
+    registry: &ItemRegistry,
+) {
+    match burner {
+        Some(burner) if burner.accepts(&item) => burner.stored += 1,
+        _ => {
+            inventory.add_items(&[(item, 1).into()], registry);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    common::{Facing, GameState, Paused},
+    crafting::{handle_crafting, Crafter, CrafterState, CraftingBlocked, ItemsConsumedEvent},
+    items::{
+        definitions::ItemRegistry,
+        inventory::{Inventory, ItemAmount},
+        ItemType,
+    },
+    map::grid::GridPosition,
+    simulation::{seconds_to_ticks, ticks_to_seconds},
+};
+
+const FUEL_BURN_SECONDS: f32 = 6.0;
+// Fuel items the burner's own slot holds
+const FUEL_CAPACITY: u32 = 20;
+const TIMER_Z: f32 = 2.0;
+
+pub(super) struct SmelterPlugin;
+
+impl Plugin for SmelterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            burn_fuel
+                .before(handle_crafting)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (attach_burn_timer, update_burn_timer)
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .register_type::<Burner>();
+    }
+}
+
+/// Burns fuel from its own slot while the structure crafts; crafting stalls without it.
+/// Fuel never enters the structure's inventory, so it can't crowd out inputs or outputs.
+#[derive(Component, Reflect, Clone, Debug)]
+pub struct Burner {
+    pub fuel: ItemType,
+    // Ticks of crafting one fuel item is good for
+    pub burn_ticks: u32,
+    pub remaining: u32,
+    pub burning: bool,
+    pub stored: u32,
+    pub capacity: u32,
+}
+
+impl Burner {
+    /// Whether the item is this burner's fuel and its slot has room for one more.
+    pub fn accepts(&self, item: &ItemType) -> bool {
+        *item == self.fuel && self.stored < self.capacity
+    }
+
+    pub fn stored_fuel(&self) -> Option<ItemAmount> {
+        (self.stored > 0).then(|| (self.fuel, self.stored).into())
+    }
+}
+
+impl Default for Burner {
+    fn default() -> Self {
+        Burner {
+            fuel: ItemType::WOOD,
+            burn_ticks: seconds_to_ticks(FUEL_BURN_SECONDS),
+            remaining: 0,
+            burning: false,
+            stored: 0,
+            capacity: FUEL_CAPACITY,
+        }
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct SmelterBundle {
+    pub burner: Burner,
+    pub blocked: CraftingBlocked,
+}
+
+#[derive(Component)]
+struct BurnTimerText;
+
+pub fn burn_fuel(
+    mut smelters: Query<(&mut Burner, &mut CraftingBlocked, &Inventory, &Crafter), Without<Paused>>,
+    mut consumed: EventWriter<ItemsConsumedEvent>,
+    registry: Res<ItemRegistry>,
+) {
+    for (mut burner, mut blocked, inventory, crafter) in &mut smelters {
+        // Fuel only burns while there is something to smelt and room for the result
+        let smelting = match (&crafter.state, crafter.recipe.as_ref()) {
+            (CrafterState::Assembling(_), Some(recipe)) => {
+                !crafter.is_finished(recipe) && inventory.has_space_for(&recipe.output, &registry)
+            }
+            _ => false,
+        };
+        if !smelting {
+            if burner.burning {
+                burner.burning = false;
+            }
+            // Finishing a craft doesn't need the fire, so it is never held up by fuel
+            if blocked.0 {
+                blocked.0 = false;
+            }
+            continue;
+        }
+        if burner.remaining == 0 {
+            if burner.stored == 0 {
+                burner.burning = false;
+                // The craft stalls until there is fuel again
+                blocked.0 = true;
+                continue;
+            }
+            burner.stored -= 1;
+            burner.remaining += burner.burn_ticks;
+            consumed.send(ItemsConsumedEvent {
+                items: vec![(burner.fuel, 1).into()],
+            });
+        }
+        burner.burning = true;
+        if blocked.0 {
+            blocked.0 = false;
+        }
+        burner.remaining -= 1;
+    }
+}
+
+// The timer sits above the structure and stays upright whichever way it faces
+fn attach_burn_timer(mut commands: Commands, smelters: Query<(Entity, &Facing), Added<Burner>>) {
+    for (entity, facing) in &smelters {
+        let upright = facing.rotation().inverse();
+        let offset = Vec3::new(0.0, GridPosition::PIXELS_PER_TILE as f32 / 2.0, TIMER_Z);
+        commands.entity(entity).with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 8.0,
+                            color: Color::ORANGE_RED,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform {
+                        translation: upright * offset,
+                        rotation: upright,
+                        ..default()
+                    },
+                    text_anchor: Anchor::BottomCenter,
+                    ..default()
+                },
+                BurnTimerText,
+            ));
+        });
+    }
+}
+
+fn update_burn_timer(
+    smelters: Query<(&Burner, &Children)>,
+    mut labels: Query<&mut Text, With<BurnTimerText>>,
+) {
+    for (burner, children) in &smelters {
+        let label = match (burner.remaining > 0, burner.stored) {
+            (false, 0) => "no fuel".to_string(),
+            (false, fuel) => format!("fuel x{}", fuel),
+            (true, fuel) => format!("{:.1}s +{}", ticks_to_seconds(burner.remaining), fuel),
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = labels.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
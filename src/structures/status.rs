@@ -0,0 +1,271 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{
+    common::{Facing, GameState, Paused},
+    crafting::{Crafter, CrafterState},
+    items::{
+        definitions::ItemRegistry,
+        inventory::Inventory,
+        item_spawner::{ItemSpawnSource, ItemSpawner},
+    },
+    map::{grid::GridPosition, resource_nodes::ResourceNodes},
+};
+
+use super::{smelter::Burner, Structure};
+
+const BAR_HEIGHT: f32 = 4.0;
+const BAR_Z: f32 = 2.0;
+const BAR_COLOR: Color = Color::rgb(0.4, 0.9, 0.5);
+const BAR_BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+const ICON_SIZE: f32 = 12.0;
+
+pub(super) struct StructureStatusPlugin;
+
+impl Plugin for StructureStatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                attach_status_display,
+                update_structure_status,
+                update_status_display,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .register_type::<StructureStatus>();
+    }
+}
+
+/// Why a crafter or spawner isn't making progress, if it is stuck.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StructureStatus {
+    #[default]
+    Working,
+    Paused,
+    NoRecipe,
+    MissingInputs,
+    OutputFull,
+    NoFuel,
+}
+
+impl StructureStatus {
+    pub fn icon_file(&self) -> Option<&'static str> {
+        use StructureStatus::*;
+        match self {
+            Working | Paused => None,
+            NoRecipe => Some("icons/status/no_recipe.png"),
+            MissingInputs => Some("icons/status/missing_inputs.png"),
+            OutputFull => Some("icons/status/output_full.png"),
+            NoFuel => Some("icons/status/no_fuel.png"),
+        }
+    }
+}
+
+// How far along the current craft or gather is, from 0 to 1
+#[derive(Component)]
+struct ProgressBarFill {
+    width: f32,
+}
+
+#[derive(Component)]
+struct StatusIcon;
+
+// New structures that do timed work and so get a progress bar
+type NewWorkingStructure = (Added<Structure>, Or<(With<Crafter>, With<ItemSpawner>)>);
+
+// Children are spawned upright and below the structure whichever way it faces
+fn attach_status_display(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    structures: Query<(Entity, &Structure, &Facing), NewWorkingStructure>,
+) {
+    for (entity, structure, facing) in &structures {
+        let size =
+            structure.0.rotated_footprint(*facing).as_vec2() * GridPosition::PIXELS_PER_TILE as f32;
+        let upright = facing.rotation().inverse();
+        let bar_offset = Vec3::new(0.0, -size.y / 2.0 + BAR_HEIGHT, BAR_Z);
+        let icon_offset = Vec3::new(
+            size.x / 2.0 - ICON_SIZE / 2.0,
+            size.y / 2.0 - ICON_SIZE / 2.0,
+            BAR_Z,
+        );
+        let width = size.x - 4.0;
+
+        commands
+            .entity(entity)
+            .insert(StructureStatus::default())
+            .with_children(|parent| {
+                parent
+                    .spawn(SpriteBundle {
+                        sprite: Sprite {
+                            color: BAR_BACKGROUND_COLOR,
+                            custom_size: Some(Vec2::new(width, BAR_HEIGHT)),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: upright * bar_offset,
+                            rotation: upright,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|background| {
+                        background.spawn((
+                            SpriteBundle {
+                                sprite: Sprite {
+                                    color: BAR_COLOR,
+                                    custom_size: Some(Vec2::new(0.0, BAR_HEIGHT)),
+                                    anchor: Anchor::CenterLeft,
+                                    ..default()
+                                },
+                                transform: Transform::from_xyz(-width / 2.0, 0.0, 0.1),
+                                ..default()
+                            },
+                            ProgressBarFill { width },
+                        ));
+                    });
+                parent.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            custom_size: Some(Vec2::splat(ICON_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: upright * icon_offset,
+                            rotation: upright,
+                            ..default()
+                        },
+                        texture: asset_server.load(StructureStatus::NoRecipe.icon_file().unwrap()),
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    StatusIcon,
+                ));
+            });
+    }
+}
+
+fn crafter_status(
+    crafter: &Crafter,
+    burner: Option<&Burner>,
+    inventory: &Inventory,
+    registry: &ItemRegistry,
+) -> StructureStatus {
+    let Some(recipe) = crafter.recipe.as_ref() else {
+        return StructureStatus::NoRecipe;
+    };
+    match crafter.state {
+        CrafterState::Idle => StructureStatus::Working,
+        CrafterState::Pending(_) if !inventory.has_items(&recipe.input) => {
+            StructureStatus::MissingInputs
+        }
+        CrafterState::Assembling(_)
+            if crafter.is_finished(recipe)
+                || !inventory.has_space_for(&recipe.output, registry) =>
+        {
+            StructureStatus::OutputFull
+        }
+        CrafterState::Assembling(_) if burner.is_some_and(|burner| !burner.burning) => {
+            StructureStatus::NoFuel
+        }
+        _ => StructureStatus::Working,
+    }
+}
+
+fn spawner_status(
+    spawner: &ItemSpawner,
+    source: Option<&ItemSpawnSource>,
+    nodes: &ResourceNodes,
+    inventory: &Inventory,
+    registry: &ItemRegistry,
+) -> StructureStatus {
+    if !inventory.has_space_for(&spawner.output, registry) {
+        return StructureStatus::OutputFull;
+    }
+    match source {
+        Some(source) if source.is_depleted(nodes) => StructureStatus::MissingInputs,
+        _ => StructureStatus::Working,
+    }
+}
+
+// Everything a structure's status is worked out from
+type StatusSources = (
+    &'static mut StructureStatus,
+    &'static Inventory,
+    Option<&'static Crafter>,
+    Option<&'static Burner>,
+    Option<&'static ItemSpawner>,
+    Option<&'static ItemSpawnSource>,
+    Option<&'static Paused>,
+);
+
+fn update_structure_status(
+    mut structures: Query<StatusSources>,
+    nodes: Res<ResourceNodes>,
+    registry: Res<ItemRegistry>,
+) {
+    for (mut status, inventory, crafter, burner, spawner, source, paused) in &mut structures {
+        let current = match (paused, crafter, spawner) {
+            (Some(_), _, _) => StructureStatus::Paused,
+            (None, Some(crafter), _) => crafter_status(crafter, burner, inventory, &registry),
+            (None, None, Some(spawner)) => {
+                spawner_status(spawner, source, &nodes, inventory, &registry)
+            }
+            (None, None, None) => StructureStatus::Working,
+        };
+        // Only touch the status when it changes so the icon isn't reloaded every frame
+        if *status != current {
+            *status = current;
+        }
+    }
+}
+
+type StatusDisplay = (
+    Ref<'static, StructureStatus>,
+    Option<&'static Crafter>,
+    Option<&'static ItemSpawner>,
+    &'static Children,
+);
+
+fn update_status_display(
+    asset_server: Res<AssetServer>,
+    structures: Query<StatusDisplay>,
+    children: Query<&Children>,
+    mut fills: Query<(&ProgressBarFill, &mut Sprite)>,
+    mut icons: Query<(&mut Handle<Image>, &mut Visibility), With<StatusIcon>>,
+) {
+    for (status, crafter, spawner, structure_children) in &structures {
+        let progress = match (crafter, spawner) {
+            (Some(crafter), _) => match (&crafter.state, crafter.recipe.as_ref()) {
+                (CrafterState::Assembling(_), Some(recipe)) => crafter.progress_fraction(recipe),
+                _ => 0.0,
+            },
+            (None, Some(spawner)) => spawner.timer.fraction(),
+            (None, None) => 0.0,
+        }
+        .clamp(0.0, 1.0);
+
+        for child in structure_children.iter() {
+            // The fill sits inside the bar background
+            for grandchild in children.get(*child).into_iter().flat_map(|c| c.iter()) {
+                if let Ok((fill, mut sprite)) = fills.get_mut(*grandchild) {
+                    sprite.custom_size = Some(Vec2::new(fill.width * progress, BAR_HEIGHT));
+                }
+            }
+            if !status.is_changed() {
+                continue;
+            }
+            let Ok((mut texture, mut visibility)) = icons.get_mut(*child) else {
+                continue;
+            };
+            match status.icon_file() {
+                Some(file) => {
+                    *texture = asset_server.load(file);
+                    *visibility = Visibility::Inherited;
+                }
+                None => *visibility = Visibility::Hidden,
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::GameState,
+    crafting::{Crafter, CrafterState},
+    player::{
+        crafting_queue::{CancelCraftEvent, CraftingQueue},
+        Player,
+    },
+};
+
+use super::{text_style, PANEL_COLOR, SLOT_COLOR};
+
+pub(super) struct CraftingQueuePanelPlugin;
+
+impl Plugin for CraftingQueuePanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_crafting_queue_panel)
+            .add_systems(
+                Update,
+                (
+                    cancel_clicked_craft,
+                    update_crafting_queue_entries,
+                    update_crafting_progress,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component)]
+struct CraftingQueuePanel;
+
+#[derive(Component)]
+struct CraftingQueueList;
+
+#[derive(Component)]
+struct CraftingProgressText;
+
+// Clicking an entry cancels it
+#[derive(Component)]
+struct CraftingQueueEntry(u32);
+
+fn spawn_crafting_queue_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            // Lets the panel block clicks from reaching the world below it
+            Interaction::default(),
+            CraftingQueuePanel,
+            Name::from("Crafting Queue Panel"),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_section("", text_style(14.0)),
+                CraftingProgressText,
+            ));
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                CraftingQueueList,
+            ));
+        });
+}
+
+fn cancel_clicked_craft(
+    entries: Query<(&Interaction, &CraftingQueueEntry), Changed<Interaction>>,
+    mut events: EventWriter<CancelCraftEvent>,
+) {
+    for (interaction, entry) in &entries {
+        if *interaction == Interaction::Pressed {
+            events.send(CancelCraftEvent(entry.0));
+        }
+    }
+}
+
+fn update_crafting_queue_entries(
+    mut commands: Commands,
+    player: Query<&CraftingQueue, (With<Player>, Changed<CraftingQueue>)>,
+    mut panel: Query<&mut Visibility, With<CraftingQueuePanel>>,
+    list: Query<Entity, With<CraftingQueueList>>,
+) {
+    let Ok(queue) = player.get_single() else {
+        return;
+    };
+    *panel.single_mut() = match queue.entries.is_empty() {
+        true => Visibility::Hidden,
+        false => Visibility::Inherited,
+    };
+
+    let list = list.single();
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|list| {
+        for entry in queue.entries.iter() {
+            // Intermediates are indented under what they are made for
+            let indent = match entry.parent {
+                Some(_) => 12.0,
+                None => 0.0,
+            };
+            list.spawn((
+                ButtonBundle {
+                    style: Style {
+                        margin: UiRect::left(Val::Px(indent)),
+                        padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                        ..default()
+                    },
+                    background_color: SLOT_COLOR.into(),
+                    ..default()
+                },
+                CraftingQueueEntry(entry.id),
+            ))
+            .with_children(|button| {
+                button.spawn(TextBundle::from_section(
+                    format!("{} x{}", entry.recipe, entry.remaining),
+                    text_style(12.0),
+                ));
+            });
+        }
+    });
+}
+
+fn update_crafting_progress(
+    player: Query<&Crafter, With<Player>>,
+    mut text: Query<&mut Text, With<CraftingProgressText>>,
+) {
+    let crafter = player.single();
+    let progress = match (&crafter.state, crafter.recipe.as_ref()) {
+        (CrafterState::Assembling(_), Some(recipe)) => format!(
+            "Crafting {}: {:.0}%",
+            recipe.id,
+            crafter.progress_fraction(recipe) * 100.0
+        ),
+        _ => "Crafting queue".to_string(),
+    };
+    text.single_mut().sections[0].value = progress;
+}
@@ -0,0 +1,198 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{text_input_unfocused, GameState, Held, Holdable},
+    items::{definitions::ItemRegistry, inventory::Inventory, ItemType},
+    player::Player,
+};
+
+use super::{PANEL_COLOR, SELECTED_SLOT_COLOR, SLOT_COLOR, TEXT_COLOR};
+
+const TOGGLE_KEY: KeyCode = KeyCode::I;
+const SLOT_SIZE: f32 = 40.0;
+const SLOTS_PER_ROW: f32 = 5.0;
+
+pub(super) struct InventoryPanelPlugin;
+
+impl Plugin for InventoryPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_inventory_panel)
+            .add_systems(
+                Update,
+                (
+                    toggle_inventory_panel.run_if(text_input_unfocused),
+                    select_inventory_slot,
+                    update_inventory_slots,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .register_type::<InventorySlot>();
+    }
+}
+
+#[derive(Component)]
+pub struct InventoryPanel;
+
+// The node the slots are rebuilt under whenever the player's inventory changes
+#[derive(Component)]
+struct InventorySlotGrid;
+
+/// A slot in the inventory panel and the item stacked in it, if any.
+#[derive(Component, Reflect, Debug)]
+pub struct InventorySlot(pub Option<ItemType>);
+
+fn spawn_inventory_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            // Lets the panel block clicks from reaching the world below it
+            Interaction::default(),
+            InventoryPanel,
+            Name::from("Inventory Panel"),
+        ))
+        .with_children(|panel| {
+            panel.spawn(TextBundle::from_section(
+                "Inventory",
+                TextStyle {
+                    font_size: 16.0,
+                    color: TEXT_COLOR,
+                    ..default()
+                },
+            ));
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px((SLOT_SIZE + 4.0) * SLOTS_PER_ROW),
+                        flex_wrap: FlexWrap::Wrap,
+                        row_gap: Val::Px(4.0),
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                InventorySlotGrid,
+            ));
+        });
+}
+
+fn toggle_inventory_panel(
+    keys: Res<Input<KeyCode>>,
+    mut panel: Query<&mut Visibility, With<InventoryPanel>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    for mut visibility in &mut panel {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn select_inventory_slot(
+    slots: Query<(&Interaction, &InventorySlot), Changed<Interaction>>,
+    mut held: Query<&mut Held, With<Player>>,
+) {
+    for (interaction, slot) in &slots {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let mut held = held.single_mut();
+        *held = Held(slot.0.map(Holdable::Item));
+        println!("Held: {:?}", held);
+    }
+}
+
+type PlayerSlotsChanged = (With<Player>, Or<(Changed<Inventory>, Changed<Held>)>);
+
+fn update_inventory_slots(
+    mut commands: Commands,
+    player: Query<(&Inventory, &Held), With<Player>>,
+    changed: Query<(), PlayerSlotsChanged>,
+    registry: Res<ItemRegistry>,
+    grid: Query<Entity, With<InventorySlotGrid>>,
+) {
+    // Icons only show up once the item definitions are loaded
+    if changed.is_empty() && !registry.is_changed() {
+        return;
+    }
+    let Ok((inventory, held)) = player.get_single() else {
+        return;
+    };
+    let Ok(grid) = grid.get_single() else {
+        return;
+    };
+
+    let held_item = match held.0 {
+        Some(Holdable::Item(item)) => Some(item),
+        _ => None,
+    };
+    let stacks = inventory.stacks(&registry);
+    commands.entity(grid).despawn_descendants();
+    commands.entity(grid).with_children(|grid| {
+        for index in 0..inventory.slots as usize {
+            let stack = stacks.get(index);
+            let item = stack.map(|stack| stack.item);
+            let color = match item.is_some() && item == held_item {
+                true => SELECTED_SLOT_COLOR,
+                false => SLOT_COLOR,
+            };
+            grid.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(SLOT_SIZE),
+                        height: Val::Px(SLOT_SIZE),
+                        justify_content: JustifyContent::FlexEnd,
+                        align_items: AlignItems::FlexEnd,
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                InventorySlot(item),
+            ))
+            .with_children(|slot| {
+                let Some(stack) = stack else {
+                    return;
+                };
+                if let Some(icon) = registry.icon(&stack.item) {
+                    slot.spawn(ImageBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(4.0),
+                            top: Val::Px(4.0),
+                            width: Val::Px(SLOT_SIZE - 8.0),
+                            height: Val::Px(SLOT_SIZE - 8.0),
+                            ..default()
+                        },
+                        image: UiImage::new(icon),
+                        ..default()
+                    });
+                }
+                slot.spawn(TextBundle::from_section(
+                    stack.amount.unwrap_or(0).to_string(),
+                    TextStyle {
+                        font_size: 12.0,
+                        color: TEXT_COLOR,
+                        ..default()
+                    },
+                ));
+            });
+        }
+    });
+}
@@ -0,0 +1,91 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    common::{GameState, MainMenuUI},
+    save::{latest_save, LoadGameEvent},
+};
+
+use super::{spawn_button, text_style, ButtonSize};
+
+const MENU_BACKGROUND_COLOR: Color = Color::rgb(0.08, 0.06, 0.12);
+
+pub(super) struct MainMenuPlugin;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::MainMenu), spawn_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), despawn_main_menu)
+            .add_systems(
+                Update,
+                handle_main_menu_buttons.run_if(in_state(GameState::MainMenu)),
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+enum MainMenuButton {
+    NewGame,
+    Load,
+    Quit,
+}
+
+fn spawn_main_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: MENU_BACKGROUND_COLOR.into(),
+                // Draw over the world and the gameplay panels
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            Interaction::default(),
+            MainMenuUI,
+            Name::from("Main Menu"),
+        ))
+        .with_children(|menu| {
+            menu.spawn(TextBundle::from_section("Fae Factory", text_style(40.0)));
+            spawn_button(menu, "New Game", ButtonSize::Menu, MainMenuButton::NewGame);
+            spawn_button(menu, "Load", ButtonSize::Menu, MainMenuButton::Load);
+            spawn_button(menu, "Quit", ButtonSize::Menu, MainMenuButton::Quit);
+        });
+}
+
+fn despawn_main_menu(mut commands: Commands, menus: Query<Entity, With<MainMenuUI>>) {
+    for menu in &menus {
+        commands.entity(menu).despawn_recursive();
+    }
+}
+
+fn handle_main_menu_buttons(
+    buttons: Query<(&Interaction, &MainMenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut load_events: EventWriter<LoadGameEvent>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            // The world is generated at startup, so a new game just starts playing it
+            MainMenuButton::NewGame => next_state.set(GameState::Playing),
+            MainMenuButton::Load => match latest_save() {
+                Some(path) => {
+                    load_events.send(LoadGameEvent(path));
+                    next_state.set(GameState::Playing);
+                }
+                None => println!("No saved game to load"),
+            },
+            MainMenuButton::Quit => exit.send(AppExit),
+        }
+    }
+}
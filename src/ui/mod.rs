@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+
+use crate::items::{definitions::ItemRegistry, inventory::ItemAmount};
+
+use self::{
+    crafting_queue::CraftingQueuePanelPlugin, inventory::InventoryPanelPlugin,
+    main_menu::MainMenuPlugin, pause_menu::PauseMenuPlugin, rate_calculator::RateCalculatorPlugin,
+    recipe_picker::RecipePickerPlugin, statistics_panel::StatisticsPanelPlugin,
+    structure_panel::StructurePanelPlugin,
+};
+
+pub mod crafting_queue;
+pub mod inventory;
+pub mod main_menu;
+pub mod pause_menu;
+pub mod rate_calculator;
+pub mod recipe_picker;
+pub mod statistics_panel;
+pub mod structure_panel;
+
+pub const PANEL_COLOR: Color = Color::rgba(0.1, 0.08, 0.15, 0.85);
+pub const SLOT_COLOR: Color = Color::rgb(0.25, 0.22, 0.3);
+pub const SELECTED_SLOT_COLOR: Color = Color::rgb(0.55, 0.45, 0.7);
+pub const TEXT_COLOR: Color = Color::rgb(0.95, 0.92, 1.0);
+
+pub struct FaeUiPlugin;
+
+impl Plugin for FaeUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            InventoryPanelPlugin,
+            StructurePanelPlugin,
+            RecipePickerPlugin,
+            CraftingQueuePanelPlugin,
+            MainMenuPlugin,
+            PauseMenuPlugin,
+            StatisticsPanelPlugin,
+            RateCalculatorPlugin,
+        ));
+    }
+}
+
+/// Whether the cursor is over any interactive ui, so world clicks can be ignored.
+pub fn cursor_over_ui(interactions: &Query<&Interaction>) -> bool {
+    interactions
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+}
+
+pub fn text_style(font_size: f32) -> TextStyle {
+    TextStyle {
+        font_size,
+        color: TEXT_COLOR,
+        ..default()
+    }
+}
+
+/// Menu buttons are wide, panel buttons small enough to fit a row of them.
+#[derive(Debug, Clone, Copy)]
+pub enum ButtonSize {
+    Menu,
+    Panel,
+}
+
+/// Spawns a labelled button tagged with `marker`, its label as the only child.
+pub fn spawn_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    size: ButtonSize,
+    marker: impl Component,
+) {
+    let (style, font_size) = match size {
+        ButtonSize::Menu => (
+            Style {
+                width: Val::Px(160.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            18.0,
+        ),
+        ButtonSize::Panel => (
+            Style {
+                padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+                ..default()
+            },
+            12.0,
+        ),
+    };
+    parent
+        .spawn((
+            ButtonBundle {
+                style,
+                background_color: SLOT_COLOR.into(),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section(label, text_style(font_size)));
+        });
+}
+
+/// Lists amounts by display name, e.g. `3 Wood, 1 Crystal`.
+pub fn describe_items(amounts: &[ItemAmount], registry: &ItemRegistry) -> String {
+    amounts
+        .iter()
+        .map(|amount| {
+            format!(
+                "{} {}",
+                amount.amount.unwrap_or(0),
+                registry.name(&amount.item)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
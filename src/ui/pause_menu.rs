@@ -0,0 +1,98 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::common::{text_input_unfocused, GameState};
+
+use super::{spawn_button, text_style, ButtonSize, PANEL_COLOR};
+
+pub(super) struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Paused), spawn_pause_menu)
+            .add_systems(OnExit(GameState::Paused), despawn_pause_menu)
+            .add_systems(
+                Update,
+                (
+                    toggle_pause.run_if(text_input_unfocused.and_then(
+                        in_state(GameState::Playing).or_else(in_state(GameState::Paused)),
+                    )),
+                    handle_pause_menu_buttons.run_if(in_state(GameState::Paused)),
+                ),
+            );
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component, Clone, Copy, Debug)]
+enum PauseMenuButton {
+    Resume,
+    Quit,
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(10.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            Interaction::default(),
+            PauseMenu,
+            Name::from("Pause Menu"),
+        ))
+        .with_children(|menu| {
+            menu.spawn(TextBundle::from_section("Paused", text_style(32.0)));
+            spawn_button(menu, "Resume", ButtonSize::Menu, PauseMenuButton::Resume);
+            spawn_button(menu, "Quit", ButtonSize::Menu, PauseMenuButton::Quit);
+        });
+}
+
+fn despawn_pause_menu(mut commands: Commands, menus: Query<Entity, With<PauseMenu>>) {
+    for menu in &menus {
+        commands.entity(menu).despawn_recursive();
+    }
+}
+
+// Escape pauses and resumes
+fn toggle_pause(
+    keys: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        GameState::MainMenu => (),
+    }
+}
+
+fn handle_pause_menu_buttons(
+    buttons: Query<(&Interaction, &PauseMenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            PauseMenuButton::Resume => next_state.set(GameState::Playing),
+            PauseMenuButton::Quit => exit.send(AppExit),
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    common::{text_input_unfocused, GameState},
+    crafting::CrafterSpeed,
+    items::{definitions::ItemRegistry, item_spawner::ItemSpawnSpeed, ItemType},
+    rates::{Producer, RateCalculator, RatePlan},
+    recipes::Recipes,
+    research::{AvailableRecipes, AvailableStructures},
+    structures::{Structure, StructureType},
+};
+
+use super::{spawn_button, text_style, ButtonSize, PANEL_COLOR};
+
+const TOGGLE_KEY: KeyCode = KeyCode::C;
+const RATE_STEP: f32 = 5.0;
+
+pub(super) struct RateCalculatorPlugin;
+
+impl Plugin for RateCalculatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RateCalculatorState::default())
+            .add_systems(Startup, spawn_rate_calculator)
+            .add_systems(
+                Update,
+                (
+                    toggle_rate_calculator.run_if(text_input_unfocused),
+                    handle_rate_calculator_buttons,
+                    update_rate_calculator,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The item and rate the calculator is planning for.
+#[derive(Resource, Debug)]
+struct RateCalculatorState {
+    item: Option<ItemType>,
+    per_minute: f32,
+}
+
+impl Default for RateCalculatorState {
+    fn default() -> Self {
+        RateCalculatorState {
+            item: None,
+            per_minute: RATE_STEP,
+        }
+    }
+}
+
+#[derive(Component)]
+struct RateCalculatorPanel;
+
+#[derive(Component)]
+struct RateCalculatorText;
+
+#[derive(Component, Clone, Copy, Debug)]
+enum RateCalculatorButton {
+    PreviousItem,
+    NextItem,
+    Slower,
+    Faster,
+}
+
+fn spawn_rate_calculator(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(35.0),
+                    top: Val::Px(10.0),
+                    width: Val::Px(340.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            // Lets the panel block clicks from reaching the world below it
+            Interaction::default(),
+            RateCalculatorPanel,
+            Name::from("Rate Calculator"),
+        ))
+        .with_children(|panel| {
+            panel.spawn(TextBundle::from_section(
+                "Rate Calculator",
+                text_style(16.0),
+            ));
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_wrap: FlexWrap::Wrap,
+                        column_gap: Val::Px(4.0),
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|buttons| {
+                    spawn_button(
+                        buttons,
+                        "< Item",
+                        ButtonSize::Menu,
+                        RateCalculatorButton::PreviousItem,
+                    );
+                    spawn_button(
+                        buttons,
+                        "Item >",
+                        ButtonSize::Menu,
+                        RateCalculatorButton::NextItem,
+                    );
+                    spawn_button(
+                        buttons,
+                        "- Rate",
+                        ButtonSize::Menu,
+                        RateCalculatorButton::Slower,
+                    );
+                    spawn_button(
+                        buttons,
+                        "Rate +",
+                        ButtonSize::Menu,
+                        RateCalculatorButton::Faster,
+                    );
+                });
+            panel.spawn((
+                TextBundle::from_section("", text_style(12.0)),
+                RateCalculatorText,
+            ));
+        });
+}
+
+fn toggle_rate_calculator(
+    keys: Res<Input<KeyCode>>,
+    mut panel: Query<&mut Visibility, With<RateCalculatorPanel>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    for mut visibility in &mut panel {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn handle_rate_calculator_buttons(
+    buttons: Query<(&Interaction, &RateCalculatorButton), Changed<Interaction>>,
+    registry: Res<ItemRegistry>,
+    mut state: ResMut<RateCalculatorState>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let mut items: Vec<ItemType> = registry.definitions.keys().copied().collect();
+        items.sort_by_key(|item| item.id());
+        let current = state
+            .item
+            .and_then(|item| items.iter().position(|other| *other == item));
+        match button {
+            RateCalculatorButton::PreviousItem if !items.is_empty() => {
+                let index = current.map_or(items.len() - 1, |index| {
+                    (index + items.len() - 1) % items.len()
+                });
+                state.item = Some(items[index]);
+            }
+            RateCalculatorButton::NextItem if !items.is_empty() => {
+                let index = current.map_or(0, |index| (index + 1) % items.len());
+                state.item = Some(items[index]);
+            }
+            RateCalculatorButton::Slower => {
+                state.per_minute = (state.per_minute - RATE_STEP).max(RATE_STEP);
+            }
+            RateCalculatorButton::Faster => state.per_minute += RATE_STEP,
+            _ => (),
+        }
+    }
+}
+
+type StructureSpeeds = (
+    &'static Structure,
+    Option<&'static CrafterSpeed>,
+    Option<&'static ItemSpawnSpeed>,
+);
+type SpeedChanged = Or<(Changed<CrafterSpeed>, Changed<ItemSpawnSpeed>)>;
+
+// Plans with the recipes and structures the player has unlocked so far, at the speed of
+// the slowest placed structure of each kind so the plan is never short of structures
+#[allow(clippy::too_many_arguments)]
+fn update_rate_calculator(
+    state: Res<RateCalculatorState>,
+    structures: Query<StructureSpeeds>,
+    changed_speeds: Query<(), SpeedChanged>,
+    mut removed_structures: RemovedComponents<Structure>,
+    recipes: Res<Recipes>,
+    available_recipes: Res<AvailableRecipes>,
+    available_structures: Res<AvailableStructures>,
+    registry: Res<ItemRegistry>,
+    mut text: Query<&mut Text, With<RateCalculatorText>>,
+) {
+    // Placing, removing or speeding up a structure changes the speeds planned with
+    let speeds_changed = removed_structures.iter().count() > 0 || !changed_speeds.is_empty();
+    let changed = state.is_changed()
+        || speeds_changed
+        || recipes.is_changed()
+        || available_recipes.is_changed()
+        || available_structures.is_changed()
+        || registry.is_changed();
+    if !changed {
+        return;
+    }
+    let Some(item) = state.item else {
+        text.single_mut().sections[0].value = "Pick an item to plan for".to_string();
+        return;
+    };
+    let mut calculator = RateCalculator::new(
+        recipes
+            .0
+            .values()
+            .filter(|recipe| available_recipes.0.contains(&recipe.id)),
+        available_structures.0.iter().copied(),
+    );
+    let mut slowest: HashMap<StructureType, u32> = HashMap::default();
+    for (structure, crafter_speed, spawn_speed) in &structures {
+        let speed = crafter_speed
+            .map(|speed| speed.0)
+            .or_else(|| spawn_speed.map(|speed| speed.0));
+        if let Some(speed) = speed {
+            slowest
+                .entry(structure.0)
+                .and_modify(|slowest| *slowest = (*slowest).min(speed))
+                .or_insert(speed);
+        }
+    }
+    for (structure, speed) in slowest {
+        calculator = calculator.with_speed(structure, speed);
+    }
+    let plan = calculator.plan(item, state.per_minute);
+    text.single_mut().sections[0].value = describe_plan(&plan, item, state.per_minute, &registry);
+}
+
+fn describe_plan(
+    plan: &RatePlan,
+    item: ItemType,
+    per_minute: f32,
+    registry: &ItemRegistry,
+) -> String {
+    let mut lines = vec![format!(
+        "{:.1} {}/min needs:",
+        per_minute,
+        registry.name(&item)
+    )];
+    for step in plan.steps.iter() {
+        let producer = match &step.producer {
+            Producer::Crafter {
+                structure: Some(structure),
+                recipe,
+            } => format!("{} ({})", structure, recipe),
+            Producer::Crafter {
+                structure: None,
+                recipe,
+            } => format!("handcrafting ({})", recipe),
+            Producer::Gatherer(structure) => structure.to_string(),
+        };
+        let outputs: Vec<String> = step
+            .outputs
+            .iter()
+            .map(|(item, rate)| format!("{:.1} {}", rate, registry.name(item)))
+            .collect();
+        lines.push(format!(
+            "  {:.2} x {} -> {}/min",
+            step.count,
+            producer,
+            outputs.join(", ")
+        ));
+    }
+    if !plan.raw_inputs.is_empty() {
+        lines.push("Raw inputs:".to_string());
+        for (item, rate) in plan.raw_inputs.iter() {
+            lines.push(format!("  {:.1} {}/min", rate, registry.name(item)));
+        }
+    }
+    lines.join("\n")
+}
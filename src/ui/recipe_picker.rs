@@ -0,0 +1,435 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{text_input_unfocused, GameState, TextInputFocused},
+    crafting::{Crafter, CraftingCategories},
+    input::{mouse::FaeEntityClickEvent, FaeEntityInputModifier},
+    items::{definitions::ItemRegistry, inventory::Inventory},
+    player::{crafting_queue::QueueCraftEvent, Player},
+    recipes::{Recipe, RecipeId, Recipes},
+    research::AvailableRecipes,
+    structures::{
+        assembler::{set_crafter_recipe, AssemblerRecipeChangedEvent},
+        Structure,
+    },
+};
+
+use super::{describe_items, text_style, PANEL_COLOR, SLOT_COLOR};
+
+// How far Shift-clicking +/- moves the handcraft count
+const HANDCRAFT_BATCH: u32 = 5;
+
+pub(super) struct RecipePickerPlugin;
+
+impl Plugin for RecipePickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RecipePicker::default())
+            .add_systems(Startup, spawn_recipe_picker)
+            .add_systems(
+                Update,
+                (
+                    open_handcraft_picker.run_if(text_input_unfocused),
+                    open_recipe_picker,
+                    type_recipe_search,
+                    handle_recipe_picker_buttons,
+                    update_recipe_picker,
+                    focus_recipe_search,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The crafter a recipe is being picked for, the current search text and, when picking for
+/// the player, how many crafts to queue.
+#[derive(Resource, Default, Debug)]
+pub struct RecipePicker {
+    pub crafter: Option<Entity>,
+    pub search: String,
+    pub count: u32,
+}
+
+impl RecipePicker {
+    pub fn open(&mut self, crafter: Entity) {
+        self.crafter = Some(crafter);
+        self.search.clear();
+        self.count = 1;
+    }
+}
+
+#[derive(Component)]
+struct RecipePickerPanel;
+
+#[derive(Component)]
+struct RecipePickerSearchText;
+
+#[derive(Component)]
+struct RecipePickerList;
+
+// Only shown when handcrafting, structures just get their recipe set
+#[derive(Component)]
+struct RecipePickerCountRow;
+
+#[derive(Component)]
+struct RecipePickerCountText;
+
+#[derive(Component, Clone, Debug)]
+enum RecipePickerButton {
+    Recipe(RecipeId),
+    Fewer,
+    More,
+    Close,
+}
+
+fn spawn_recipe_picker(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(30.0),
+                    top: Val::Px(60.0),
+                    width: Val::Percent(40.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            // Lets the dialog block clicks from reaching the world below it
+            Interaction::default(),
+            RecipePickerPanel,
+            Name::from("Recipe Picker"),
+        ))
+        .with_children(|panel| {
+            panel.spawn(TextBundle::from_section(
+                "Choose a recipe",
+                text_style(16.0),
+            ));
+            panel.spawn((
+                TextBundle::from_section("", text_style(14.0)),
+                RecipePickerSearchText,
+            ));
+            panel
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            column_gap: Val::Px(6.0),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    RecipePickerCountRow,
+                ))
+                .with_children(|row| {
+                    spawn_count_button(row, "-", RecipePickerButton::Fewer);
+                    row.spawn((
+                        TextBundle::from_section("", text_style(14.0)),
+                        RecipePickerCountText,
+                    ));
+                    spawn_count_button(row, "+", RecipePickerButton::More);
+                });
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                RecipePickerList,
+            ));
+            panel
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+                            align_self: AlignSelf::FlexEnd,
+                            ..default()
+                        },
+                        background_color: SLOT_COLOR.into(),
+                        ..default()
+                    },
+                    RecipePickerButton::Close,
+                ))
+                .with_children(|button| {
+                    button.spawn(TextBundle::from_section("Close", text_style(12.0)));
+                });
+        });
+}
+
+fn spawn_count_button(parent: &mut ChildBuilder, label: &str, button: RecipePickerButton) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+                    ..default()
+                },
+                background_color: SLOT_COLOR.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section(label, text_style(14.0)));
+        });
+}
+
+// Space opens the picker for the player to queue handcrafts
+fn open_handcraft_picker(
+    keys: Res<Input<KeyCode>>,
+    player: Query<Entity, With<Player>>,
+    mut picker: ResMut<RecipePicker>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        picker.open(player.single());
+    }
+}
+
+// Placed structures that can be given a recipe
+type RecipeStructure = (
+    With<Crafter>,
+    With<CraftingCategories>,
+    With<Structure>,
+    Without<Player>,
+);
+
+// Shift-clicking a crafter opens the picker for it
+fn open_recipe_picker(
+    mut click_event: EventReader<FaeEntityClickEvent>,
+    crafters: Query<(), RecipeStructure>,
+    mut picker: ResMut<RecipePicker>,
+) {
+    let Some(event) = click_event.iter().last() else {
+        return;
+    };
+    if !event
+        .modifiers
+        .check_only_pressed(&[FaeEntityInputModifier::Shift])
+    {
+        return;
+    }
+    if let Some(entity) = event
+        .entities
+        .iter()
+        .find(|entity| crafters.contains(**entity))
+    {
+        picker.open(*entity);
+    }
+}
+
+fn type_recipe_search(
+    mut characters: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut picker: ResMut<RecipePicker>,
+) {
+    if picker.crafter.is_none() {
+        characters.clear();
+        return;
+    }
+    for character in characters.iter() {
+        if !character.char.is_control() {
+            picker.search.push(character.char);
+        }
+    }
+    if keys.just_pressed(KeyCode::Back) {
+        picker.search.pop();
+    }
+}
+
+fn matches_search(recipe: &Recipe, search: &str, registry: &ItemRegistry) -> bool {
+    let search = search.trim().to_lowercase();
+    if search.is_empty() {
+        return true;
+    }
+    recipe.id.0.to_lowercase().contains(&search)
+        || recipe
+            .input
+            .iter()
+            .chain(recipe.output.iter())
+            .any(|amount| registry.name(&amount.item).to_lowercase().contains(&search))
+}
+
+// The recipes the picked crafter can make that match the search, in id order
+fn matching_recipes<'a>(
+    picker: &RecipePicker,
+    crafters: &Query<&CraftingCategories, With<Crafter>>,
+    recipes: &'a Recipes,
+    available_recipes: &AvailableRecipes,
+    registry: &ItemRegistry,
+) -> Vec<&'a Recipe> {
+    let Some(categories) = picker.crafter.and_then(|entity| crafters.get(entity).ok()) else {
+        return vec![];
+    };
+    recipes
+        .available_for(categories, available_recipes)
+        .into_iter()
+        .filter(|recipe| matches_search(recipe, &picker.search, registry))
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_recipe_picker_buttons(
+    buttons: Query<(&Interaction, &RecipePickerButton), Changed<Interaction>>,
+    keys: Res<Input<KeyCode>>,
+    mut picker: ResMut<RecipePicker>,
+    mut structure_crafters: Query<
+        (&mut Crafter, &mut Inventory, &CraftingCategories),
+        Without<Player>,
+    >,
+    players: Query<(), With<Player>>,
+    crafters: Query<&CraftingCategories, With<Crafter>>,
+    recipes: Res<Recipes>,
+    available_recipes: Res<AvailableRecipes>,
+    registry: Res<ItemRegistry>,
+    mut recipe_change_event: EventWriter<AssemblerRecipeChangedEvent>,
+    mut queue_craft_event: EventWriter<QueueCraftEvent>,
+) {
+    let Some(entity) = picker.crafter else {
+        return;
+    };
+    let pressed = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.clone());
+    // Shift steps the count by a handful
+    let step = match keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        true => HANDCRAFT_BATCH,
+        false => 1,
+    };
+    // Enter picks the first match
+    let picked = match pressed {
+        Some(RecipePickerButton::Recipe(id)) => recipes.get(&id),
+        Some(RecipePickerButton::Fewer) => {
+            picker.count = picker.count.saturating_sub(step).max(1);
+            return;
+        }
+        Some(RecipePickerButton::More) => {
+            picker.count += step;
+            return;
+        }
+        Some(RecipePickerButton::Close) => {
+            picker.crafter = None;
+            return;
+        }
+        None if keys.just_pressed(KeyCode::Return) => {
+            matching_recipes(&picker, &crafters, &recipes, &available_recipes, &registry)
+                .first()
+                .copied()
+        }
+        None => return,
+    };
+    let Some(recipe) = picked else {
+        return;
+    };
+    if players.contains(entity) {
+        queue_craft_event.send(QueueCraftEvent {
+            recipe: recipe.id.clone(),
+            count: picker.count.max(1),
+        });
+    } else if let Ok((mut crafter, mut inventory, categories)) = structure_crafters.get_mut(entity)
+    {
+        set_crafter_recipe(
+            entity,
+            &mut crafter,
+            &mut inventory,
+            categories,
+            recipe.clone(),
+            &registry,
+            &mut recipe_change_event,
+        );
+    }
+    picker.crafter = None;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_recipe_picker(
+    mut commands: Commands,
+    picker: Res<RecipePicker>,
+    crafters: Query<&CraftingCategories, With<Crafter>>,
+    players: Query<(), With<Player>>,
+    recipes: Res<Recipes>,
+    available_recipes: Res<AvailableRecipes>,
+    registry: Res<ItemRegistry>,
+    mut panel: Query<&mut Visibility, With<RecipePickerPanel>>,
+    mut search_text: Query<&mut Text, With<RecipePickerSearchText>>,
+    mut count_row: Query<&mut Style, With<RecipePickerCountRow>>,
+    mut count_text: Query<
+        &mut Text,
+        (With<RecipePickerCountText>, Without<RecipePickerSearchText>),
+    >,
+    list: Query<Entity, With<RecipePickerList>>,
+) {
+    if !picker.is_changed() && !available_recipes.is_changed() && !recipes.is_changed() {
+        return;
+    }
+    let mut visibility = panel.single_mut();
+    if picker.crafter.is_none() {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Inherited;
+    search_text.single_mut().sections[0].value = format!("Search: {}_", picker.search);
+    let handcrafting = picker
+        .crafter
+        .is_some_and(|entity| players.contains(entity));
+    count_row.single_mut().display = match handcrafting {
+        true => Display::Flex,
+        false => Display::None,
+    };
+    count_text.single_mut().sections[0].value = format!("Count: {}", picker.count);
+
+    let list = list.single();
+    commands.entity(list).despawn_descendants();
+    let matching = matching_recipes(&picker, &crafters, &recipes, &available_recipes, &registry);
+    commands.entity(list).with_children(|list| {
+        if matching.is_empty() {
+            list.spawn(TextBundle::from_section(
+                "No matching recipes",
+                text_style(12.0),
+            ));
+        }
+        for recipe in matching {
+            list.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::axes(Val::Px(6.0), Val::Px(3.0)),
+                        ..default()
+                    },
+                    background_color: SLOT_COLOR.into(),
+                    ..default()
+                },
+                RecipePickerButton::Recipe(recipe.id.clone()),
+            ))
+            .with_children(|button| {
+                button.spawn(TextBundle::from_sections([
+                    TextSection::new(format!("{}\n", recipe.id), text_style(14.0)),
+                    TextSection::new(
+                        format!(
+                            "{} -> {} ({:.1}s)",
+                            describe_items(&recipe.input, &registry),
+                            describe_items(&recipe.output, &registry),
+                            recipe.cost
+                        ),
+                        text_style(12.0),
+                    ),
+                ]));
+            });
+        }
+    });
+}
+
+// Keeps key bindings elsewhere from firing while the search has the keyboard
+fn focus_recipe_search(picker: Res<RecipePicker>, mut focused: ResMut<TextInputFocused>) {
+    if picker.is_changed() {
+        focused.0 = picker.crafter.is_some();
+    }
+}
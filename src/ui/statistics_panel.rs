@@ -0,0 +1,334 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{
+    common::{text_input_unfocused, GameState},
+    items::{definitions::ItemRegistry, ItemType},
+    player::Player,
+    statistics::{ProductionStatistics, StatisticsWindow},
+    structures::Structure,
+};
+
+use super::{text_style, PANEL_COLOR, SLOT_COLOR};
+
+const TOGGLE_KEY: KeyCode = KeyCode::P;
+const GRAPH_BARS: usize = 30;
+const BAR_WIDTH: f32 = 4.0;
+const GRAPH_HEIGHT: f32 = 16.0;
+const PRODUCED_COLOR: Color = Color::rgb(0.45, 0.8, 0.45);
+const CONSUMED_COLOR: Color = Color::rgb(0.85, 0.4, 0.4);
+const SELECTED_WINDOW_COLOR: Color = Color::rgb(0.55, 0.45, 0.7);
+
+pub(super) struct StatisticsPanelPlugin;
+
+impl Plugin for StatisticsPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(StatisticsPanelState::default())
+            .add_systems(Startup, spawn_statistics_panel)
+            .add_systems(
+                Update,
+                (
+                    toggle_statistics_panel.run_if(text_input_unfocused),
+                    select_statistics_window,
+                    update_statistics_panel,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+struct StatisticsPanelState {
+    window: StatisticsWindow,
+    // The simulated second the panel was last drawn for
+    shown: Option<u32>,
+}
+
+#[derive(Component)]
+struct StatisticsPanel;
+
+// Rebuilt once a simulated second while the panel is open
+#[derive(Component)]
+struct StatisticsList;
+
+#[derive(Component)]
+struct StatisticsWindowButton(StatisticsWindow);
+
+fn spawn_statistics_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            // Lets the panel block clicks from reaching the world below it
+            Interaction::default(),
+            StatisticsPanel,
+            Name::from("Statistics Panel"),
+        ))
+        .with_children(|panel| {
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(4.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|header| {
+                    header.spawn(TextBundle::from_section("Production", text_style(16.0)));
+                    for window in StatisticsWindow::ALL {
+                        header
+                            .spawn((
+                                ButtonBundle {
+                                    style: Style {
+                                        padding: UiRect::axes(Val::Px(6.0), Val::Px(2.0)),
+                                        ..default()
+                                    },
+                                    background_color: SLOT_COLOR.into(),
+                                    ..default()
+                                },
+                                StatisticsWindowButton(window),
+                            ))
+                            .with_children(|button| {
+                                button.spawn(TextBundle::from_section(
+                                    window.label(),
+                                    text_style(12.0),
+                                ));
+                            });
+                    }
+                });
+            panel.spawn((
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                StatisticsList,
+            ));
+        });
+}
+
+fn toggle_statistics_panel(
+    keys: Res<Input<KeyCode>>,
+    mut state: ResMut<StatisticsPanelState>,
+    mut panel: Query<&mut Visibility, With<StatisticsPanel>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) {
+        return;
+    }
+    for mut visibility in &mut panel {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+    state.shown = None;
+}
+
+fn select_statistics_window(
+    mut buttons: Query<(
+        Ref<Interaction>,
+        &StatisticsWindowButton,
+        &mut BackgroundColor,
+    )>,
+    mut state: ResMut<StatisticsPanelState>,
+) {
+    for (interaction, button, _) in &buttons {
+        if interaction.is_changed() && *interaction == Interaction::Pressed {
+            state.window = button.0;
+            state.shown = None;
+        }
+    }
+    for (_, button, mut color) in &mut buttons {
+        let selected = match button.0 == state.window {
+            true => SELECTED_WINDOW_COLOR,
+            false => SLOT_COLOR,
+        };
+        if color.0 != selected {
+            color.0 = selected;
+        }
+    }
+}
+
+fn update_statistics_panel(
+    mut commands: Commands,
+    mut state: ResMut<StatisticsPanelState>,
+    statistics: Res<ProductionStatistics>,
+    registry: Res<ItemRegistry>,
+    producers: Query<(Option<&Structure>, Option<&Player>)>,
+    panel: Query<&Visibility, With<StatisticsPanel>>,
+    list: Query<Entity, With<StatisticsList>>,
+) {
+    if *panel.single() == Visibility::Hidden {
+        return;
+    }
+    let second = statistics.elapsed_seconds();
+    if state.shown == Some(second) {
+        return;
+    }
+    state.shown = Some(second);
+    let window = state.window;
+    let seconds = statistics.window_seconds(window);
+
+    // Busiest items first
+    let mut items: Vec<_> = statistics
+        .items
+        .iter()
+        .map(|(item, history)| {
+            (
+                *item,
+                history.produced.per_minute(seconds),
+                history.consumed.per_minute(seconds),
+                history.produced.graph(window, GRAPH_BARS),
+                history.consumed.graph(window, GRAPH_BARS),
+            )
+        })
+        .filter(|(_, produced, consumed, _, _)| *produced > 0.0 || *consumed > 0.0)
+        .collect();
+    items.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.id().cmp(b.0.id())));
+
+    // Handcrafting counts as its own producer
+    let mut by_structure: Vec<(String, ItemType, HashSet<Entity>, f32)> = Vec::new();
+    for ((entity, item), history) in statistics.producers.iter() {
+        let name = match producers.get(*entity) {
+            Ok((Some(structure), _)) => structure.0.to_string(),
+            Ok((None, Some(_))) => "handcrafting".to_string(),
+            _ => continue,
+        };
+        let rate = history.per_minute(seconds);
+        match by_structure
+            .iter_mut()
+            .find(|(existing, existing_item, _, _)| *existing == name && existing_item == item)
+        {
+            Some((_, _, entities, total)) => {
+                entities.insert(*entity);
+                *total += rate;
+            }
+            None => by_structure.push((name, *item, HashSet::from([*entity]), rate)),
+        }
+    }
+    by_structure.retain(|(_, _, _, rate)| *rate > 0.0);
+    by_structure.sort_by(|a, b| b.3.total_cmp(&a.3));
+
+    let list = list.single();
+    commands.entity(list).despawn_descendants();
+    commands.entity(list).with_children(|list| {
+        if items.is_empty() {
+            list.spawn(TextBundle::from_section(
+                "Nothing produced yet",
+                text_style(12.0),
+            ));
+        }
+        for (item, produced, consumed, produced_graph, consumed_graph) in &items {
+            spawn_item_row(
+                list,
+                &registry,
+                item,
+                (*produced, *consumed),
+                produced_graph,
+                consumed_graph,
+            );
+        }
+        if !by_structure.is_empty() {
+            let lines: Vec<String> = by_structure
+                .iter()
+                .map(|(name, item, entities, rate)| {
+                    format!(
+                        "  {} x{} {}: {:.1}/min",
+                        name,
+                        entities.len(),
+                        registry.name(item),
+                        rate
+                    )
+                })
+                .collect();
+            list.spawn(TextBundle::from_section(
+                format!("By structure:\n{}", lines.join("\n")),
+                text_style(12.0),
+            ));
+        }
+    });
+}
+
+fn spawn_item_row(
+    list: &mut ChildBuilder,
+    registry: &ItemRegistry,
+    item: &ItemType,
+    (produced, consumed): (f32, f32),
+    produced_graph: &[u32],
+    consumed_graph: &[u32],
+) {
+    // Both graphs share a scale so they can be compared
+    let max = produced_graph
+        .iter()
+        .chain(consumed_graph.iter())
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    list.spawn(NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(1.0),
+            ..default()
+        },
+        ..default()
+    })
+    .with_children(|row| {
+        row.spawn(TextBundle::from_section(
+            format!(
+                "{}: +{:.1}/min -{:.1}/min",
+                registry.name(item),
+                produced,
+                consumed
+            ),
+            text_style(12.0),
+        ));
+        spawn_graph(row, produced_graph, max, PRODUCED_COLOR);
+        if consumed > 0.0 {
+            spawn_graph(row, consumed_graph, max, CONSUMED_COLOR);
+        }
+    });
+}
+
+fn spawn_graph(parent: &mut ChildBuilder, values: &[u32], max: u32, color: Color) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                height: Val::Px(GRAPH_HEIGHT),
+                align_items: AlignItems::FlexEnd,
+                column_gap: Val::Px(1.0),
+                ..default()
+            },
+            background_color: SLOT_COLOR.into(),
+            ..default()
+        })
+        .with_children(|graph| {
+            for value in values {
+                graph.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Percent(*value as f32 / max as f32 * 100.0),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                });
+            }
+        });
+}
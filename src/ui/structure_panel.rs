@@ -0,0 +1,388 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{GameState, Held, Holdable, Paused},
+    crafting::{Crafter, CrafterState},
+    input::mouse::FaeEntityClickEvent,
+    items::{definitions::ItemRegistry, inventory::Inventory, item_spawner::ItemSpawner},
+    player::Player,
+    research::{technology::Technologies, ResearchState},
+    simulation::{ticks_to_seconds, NORMAL_SPEED},
+    statistics::{ProductionStatistics, StatisticsWindow},
+    structures::{grabber::Grabber, library::Library, smelter::Burner, Structure},
+};
+
+use super::{
+    describe_items, recipe_picker::RecipePicker, spawn_button, text_style, ButtonSize, PANEL_COLOR,
+};
+
+// How much the swing buttons change a grabber's swing time by
+const SWING_STEP_SECONDS: f32 = 0.25;
+
+pub(super) struct StructurePanelPlugin;
+
+impl Plugin for StructurePanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InspectedStructure::default())
+            .add_systems(Startup, spawn_structure_panel)
+            .add_systems(
+                Update,
+                (
+                    open_structure_panel,
+                    handle_structure_panel_buttons,
+                    update_structure_panel,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The structure shown in the structure panel, if it is open.
+#[derive(Resource, Default, Debug)]
+pub struct InspectedStructure(pub Option<Entity>);
+
+#[derive(Component)]
+struct StructurePanel;
+
+#[derive(Component)]
+struct StructurePanelText;
+
+#[derive(Component, Clone, Debug)]
+enum StructurePanelButton {
+    Recipe,
+    Pause,
+    Empty,
+    SwingFaster,
+    SwingSlower,
+    Close,
+}
+
+fn spawn_structure_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    width: Val::Px(240.0),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(6.0)),
+                    row_gap: Val::Px(6.0),
+                    ..default()
+                },
+                background_color: PANEL_COLOR.into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            // Lets the panel block clicks from reaching the world below it
+            Interaction::default(),
+            StructurePanel,
+            Name::from("Structure Panel"),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_sections([
+                    TextSection::new("", text_style(16.0)),
+                    TextSection::new("", text_style(12.0)),
+                ]),
+                StructurePanelText,
+            ));
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|buttons| {
+                    spawn_button(
+                        buttons,
+                        "Recipe",
+                        ButtonSize::Panel,
+                        StructurePanelButton::Recipe,
+                    );
+                    spawn_button(
+                        buttons,
+                        "Pause",
+                        ButtonSize::Panel,
+                        StructurePanelButton::Pause,
+                    );
+                    spawn_button(
+                        buttons,
+                        "Empty",
+                        ButtonSize::Panel,
+                        StructurePanelButton::Empty,
+                    );
+                    spawn_button(
+                        buttons,
+                        "Faster",
+                        ButtonSize::Panel,
+                        StructurePanelButton::SwingFaster,
+                    );
+                    spawn_button(
+                        buttons,
+                        "Slower",
+                        ButtonSize::Panel,
+                        StructurePanelButton::SwingSlower,
+                    );
+                    spawn_button(
+                        buttons,
+                        "Close",
+                        ButtonSize::Panel,
+                        StructurePanelButton::Close,
+                    );
+                });
+        });
+}
+
+// A plain click on a structure opens it, unless an item is held to put into it
+fn open_structure_panel(
+    mut click_event: EventReader<FaeEntityClickEvent>,
+    held: Query<&Held, With<Player>>,
+    structures: Query<(), With<Structure>>,
+    mut inspected: ResMut<InspectedStructure>,
+) {
+    let Some(event) = click_event.iter().last() else {
+        return;
+    };
+    if !event.modifiers.check_only_pressed(&[]) {
+        return;
+    }
+    if let Some(Holdable::Item(_)) = held.single().0 {
+        return;
+    }
+    if let Some(entity) = event
+        .entities
+        .iter()
+        .find(|entity| structures.contains(**entity))
+    {
+        inspected.0 = Some(*entity);
+    }
+}
+
+// What the panel's buttons change on the inspected structure
+type StructureControls = (
+    Option<&'static mut Inventory>,
+    Option<&'static Crafter>,
+    Option<&'static mut Grabber>,
+    Option<&'static Paused>,
+);
+
+fn handle_structure_panel_buttons(
+    mut commands: Commands,
+    buttons: Query<(&Interaction, &StructurePanelButton), Changed<Interaction>>,
+    mut inspected: ResMut<InspectedStructure>,
+    mut structures: Query<StructureControls, (With<Structure>, Without<Player>)>,
+    mut player: Query<&mut Inventory, With<Player>>,
+    mut recipe_picker: ResMut<RecipePicker>,
+    registry: Res<ItemRegistry>,
+) {
+    for (interaction, button) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(entity) = inspected.0 else {
+            return;
+        };
+        let Ok((inventory, crafter, grabber, paused)) = structures.get_mut(entity) else {
+            return;
+        };
+        match button {
+            StructurePanelButton::Recipe => {
+                if crafter.is_some() {
+                    recipe_picker.open(entity);
+                }
+            }
+            StructurePanelButton::Pause => match paused {
+                Some(_) => {
+                    commands.entity(entity).remove::<Paused>();
+                }
+                None => {
+                    commands.entity(entity).insert(Paused);
+                }
+            },
+            StructurePanelButton::Empty => {
+                if let Some(mut inventory) = inventory {
+                    inventory.force_empty_into_other(player.single_mut().as_mut(), &registry);
+                }
+            }
+            StructurePanelButton::SwingFaster | StructurePanelButton::SwingSlower => {
+                if let Some(mut grabber) = grabber {
+                    let step = match button {
+                        StructurePanelButton::SwingFaster => -SWING_STEP_SECONDS,
+                        _ => SWING_STEP_SECONDS,
+                    };
+                    let seconds = (grabber.swing_seconds() + step).max(SWING_STEP_SECONDS);
+                    grabber.set_swing_seconds(seconds);
+                }
+            }
+            StructurePanelButton::Close => inspected.0 = None,
+        }
+    }
+}
+
+fn describe_crafter(crafter: &Crafter, registry: &ItemRegistry) -> String {
+    let Some(recipe) = crafter.recipe.as_ref() else {
+        return "Recipe: none\n".to_string();
+    };
+    let state = match crafter.state {
+        CrafterState::Idle => "idle",
+        CrafterState::Pending(_) => "waiting for inputs",
+        CrafterState::Assembling(_) => "assembling",
+    };
+    format!(
+        "Recipe: {}\n  {} -> {}\nState: {}\nProgress: {:.0}%\n",
+        recipe.id,
+        describe_items(&recipe.input, registry),
+        describe_items(&recipe.output, registry),
+        state,
+        crafter.progress_fraction(recipe) * 100.0,
+    )
+}
+
+// Everything the panel describes about the inspected structure
+type StructureDetails = (
+    &'static Structure,
+    Option<&'static Inventory>,
+    Option<&'static Crafter>,
+    Option<&'static ItemSpawner>,
+    Option<&'static Library>,
+    Option<&'static Burner>,
+    Option<&'static Grabber>,
+    Option<&'static Paused>,
+);
+
+#[allow(clippy::too_many_arguments)]
+fn update_structure_panel(
+    mut inspected: ResMut<InspectedStructure>,
+    registry: Res<ItemRegistry>,
+    statistics: Res<ProductionStatistics>,
+    research: Res<ResearchState>,
+    technologies: Res<Technologies>,
+    structures: Query<StructureDetails>,
+    mut panel: Query<&mut Visibility, With<StructurePanel>>,
+    mut text: Query<&mut Text, With<StructurePanelText>>,
+    mut labels: Query<&mut Text, Without<StructurePanelText>>,
+    mut buttons: Query<(&StructurePanelButton, &mut Style, &Children)>,
+) {
+    let mut visibility = panel.single_mut();
+    let inspected_structure = inspected.0.and_then(|entity| structures.get(entity).ok());
+    let Some((structure, inventory, crafter, spawner, library, burner, grabber, paused)) =
+        inspected_structure
+    else {
+        // The structure may have been removed while open
+        if inspected.0.is_some() {
+            inspected.0 = None;
+        }
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let mut details = String::new();
+    if paused.is_some() {
+        details.push_str("Paused\n");
+    }
+    if let Some(crafter) = crafter {
+        details.push_str(&describe_crafter(crafter, &registry));
+    }
+    if let Some(burner) = burner {
+        details.push_str(&format!(
+            "Burning {}: {:.1}s left, {}/{} stored\n",
+            registry.name(&burner.fuel),
+            ticks_to_seconds(burner.remaining),
+            burner.stored,
+            burner.capacity
+        ));
+    }
+    if let Some(grabber) = grabber {
+        details.push_str(&format!("Swing time: {:.2}s\n", grabber.swing_seconds()));
+    }
+    if let Some(spawner) = spawner {
+        details.push_str(&format!(
+            "Next gather in {:.1}s\n",
+            spawner.timer.remaining_seconds()
+        ));
+    }
+    if let Some(entity) = inspected.0 {
+        let seconds = statistics.window_seconds(StatisticsWindow::OneMinute);
+        let mut outputs: Vec<_> = statistics.produced_by(entity).collect();
+        outputs.sort_by_key(|(item, _)| item.id());
+        for (item, history) in outputs {
+            details.push_str(&format!(
+                "Output {}: {:.1}/min\n",
+                registry.name(item),
+                history.per_minute(seconds)
+            ));
+        }
+    }
+    if let Some(library) = library {
+        match library.technology.as_ref() {
+            Some(technology) => details.push_str(&format!(
+                "Studying {}: {}\n",
+                technology,
+                match library.studying {
+                    true => format!("{:.1}s", ticks_to_seconds(library.progress / NORMAL_SPEED)),
+                    false => "waiting for items".to_string(),
+                }
+            )),
+            None => details.push_str("No research selected\n"),
+        }
+        if let (Some(progress), Some(technology)) = (
+            research.current.as_ref(),
+            research.current_technology(&technologies),
+        ) {
+            details.push_str(&format!(
+                "Research progress: {}/{} units\n",
+                progress.units_completed, technology.units
+            ));
+        }
+    }
+    if let Some(inventory) = inventory {
+        details.push_str(&format!(
+            "Inventory ({}/{} slots):\n",
+            inventory.used_slots(&registry),
+            inventory.slots
+        ));
+        for stack in inventory.stacks(&registry) {
+            details.push_str(&format!(
+                "  {} x{}\n",
+                registry.name(&stack.item),
+                stack.amount.unwrap_or(0)
+            ));
+        }
+    }
+
+    let mut text = text.single_mut();
+    text.sections[0].value = format!("{}\n", structure.0);
+    text.sections[1].value = details;
+    // Only crafters have a recipe to pick and only grabbers swing
+    for (button, mut style, children) in &mut buttons {
+        let shown = match button {
+            StructurePanelButton::Recipe => crafter.is_some(),
+            StructurePanelButton::SwingFaster | StructurePanelButton::SwingSlower => {
+                grabber.is_some()
+            }
+            StructurePanelButton::Pause => {
+                let label = children
+                    .first()
+                    .and_then(|child| labels.get_mut(*child).ok());
+                if let Some(mut label) = label {
+                    label.sections[0].value = match paused {
+                        Some(_) => "Resume".to_string(),
+                        None => "Pause".to_string(),
+                    };
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        style.display = match shown {
+            true => Display::Flex,
+            false => Display::None,
+        };
+    }
+}